@@ -0,0 +1,85 @@
+/*
+ * Hand-written, not generated by openapi-generator, see `.openapi-generator-ignore`
+ */
+
+use futures::stream::{self, Stream};
+
+use crate::apis::{configuration, import_api, Error};
+use crate::models;
+
+/// default number of items requested per page
+pub const DEFAULT_PAGE_SIZE: i32 = 100;
+
+/// Walks every page of a paginated (Sieve) endpoint, starting from the first one, until it's exhausted.
+/// `fetch` gets the 1-based page number and should request exactly `page_size` items.
+/// A page shorter than `page_size` is the last one. A page identical to the previous one ends the stream as well,
+/// as it means the server ignored the paging parameters and already sent everything.
+pub fn paginate<T, E, F, Fut>(page_size: i32, fetch: F) -> impl Stream<Item = Result<Vec<T>, Error<E>>>
+where
+    T: Clone + PartialEq,
+    F: FnMut(i32) -> Fut,
+    Fut: std::future::Future<Output = Result<Vec<T>, Error<E>>>,
+{
+    struct State<T, F> {
+        fetch: F,
+        page: i32,
+        previous: Option<Vec<T>>,
+        exhausted: bool,
+    }
+
+    let state = State { fetch, page: 1, previous: None, exhausted: false };
+    stream::unfold(state, move |mut state| async move {
+        if state.exhausted {
+            return None;
+        }
+        let items = match (state.fetch)(state.page).await {
+            Ok(items) => items,
+            Err(e) => {
+                state.exhausted = true;
+                return Some((Err(e), state));
+            }
+        };
+        if state.previous.as_ref() == Some(&items) {
+            return None;
+        }
+        state.exhausted = items.len() < page_size as usize;
+        state.page += 1;
+        state.previous = Some(items.clone());
+        Some((Ok(items), state))
+    })
+}
+
+/// Every page of [`import_api::api_import_users_get`], `page_size` users each.
+pub fn api_import_users_pages<'a>(configuration: &'a configuration::Configuration, filters: Option<&'a str>, sorts: Option<&'a str>, page_size: i32) -> impl Stream<Item = Result<Vec<models::ImportIndexUsersResponse>, Error<import_api::ApiImportUsersGetError>>> + 'a {
+    paginate(page_size, move |page| import_api::api_import_users_get(configuration, filters, sorts, Some(page), Some(page_size)))
+}
+
+#[cfg(test)]
+fn fetch_from(items: Vec<i32>, page_size: i32) -> impl FnMut(i32) -> std::future::Ready<Result<Vec<i32>, Error<()>>> {
+    move |page| {
+        let start = ((page - 1) * page_size) as usize;
+        let page = items.iter().skip(start).take(page_size as usize).copied().collect();
+        std::future::ready(Ok(page))
+    }
+}
+
+#[cfg(test)]
+fn collect_pages(stream: impl Stream<Item = Result<Vec<i32>, Error<()>>>) -> Vec<Vec<i32>> {
+    use futures::StreamExt;
+    futures::executor::block_on(stream.map(|page| page.unwrap()).collect())
+}
+
+#[test]
+fn walks_all_pages() {
+    let pages = collect_pages(paginate(2, fetch_from((1..=5).collect(), 2)));
+    assert_eq!(pages, vec![vec![1, 2], vec![3, 4], vec![5]]);
+
+    let pages = collect_pages(paginate(2, fetch_from((1..=4).collect(), 2)));
+    assert_eq!(pages, vec![vec![1, 2], vec![3, 4], vec![]]);
+}
+
+#[test]
+fn stops_if_paging_is_ignored() {
+    let pages = collect_pages(paginate(2, |_| std::future::ready(Ok::<_, Error<()>>(vec![1, 2, 3]))));
+    assert_eq!(pages, vec![vec![1, 2, 3]]);
+}
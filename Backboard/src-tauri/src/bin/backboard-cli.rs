@@ -0,0 +1,330 @@
+//! # Backboard CLI
+//! headless version of the grade import, for scheduled tasks on servers without a desktop\
+//! runs the very same [pipeline](backboard::importer::import_grades) as the GUI, printing its progress to `stdout`
+//!
+//! every flag can be given as an environment variable as well, see `backboard-cli --help`\
+//! `Ctrl+C` stops the import once the in-flight uploads are finished, it can be resumed later\
+//! with `--roster-only`, only the names and classes of the students are pushed, see [`importer::sync_roster`]\
+//! with `--coverage`, the students of `--students` who haven't registered yet are listed, see [`importer::check_coverage`]
+//!
+//! exit codes:
+//! - `0`: success
+//! - `1`: any other error
+//! - `2`: invalid arguments
+//! - `3`: invalid import key
+//! - `4`: invalid csv, xlsx or mapping profile, or invalid rows without `--skip-invalid-rows`
+//! - `5`: server error (or unreachable server)
+//! - `6`: the data of some users couldn't be imported, see `--report`
+//! - `130`: cancelled with `Ctrl+C`
+
+use backboard::filter::GradeFilter;
+use backboard::history;
+use backboard::importer::{self, ImportError, ImportEvent, ImportOptions, RosterOptions};
+use backboard::report::ImportReport;
+use chrono::NaiveDate;
+use clap::Parser;
+use std::path::Path;
+use std::process::ExitCode;
+use tokio_util::sync::CancellationToken;
+
+#[derive(Debug, Parser)]
+#[command(
+    version,
+    about = "Backboard - headless grade importer for LovassyApp",
+    after_help = "The server keeps only the latest import of a user until they log in: a filtered import \
+                  (--include-*, --exclude-*, --since, --until) replaces a pending full one, \
+                  the grades left out of it only arrive with the next full import."
+)]
+struct Args {
+    /// grades export from E-Kreta, can be given several times (comma-separated as an environment variable),
+    /// overlapping exports are merged without duplicates
+    #[arg(
+        long,
+        env = "BACKBOARD_GRADES_FILE",
+        value_delimiter = ',',
+        required_unless_present_any = ["roster_only", "coverage"]
+    )]
+    grades: Vec<String>,
+    /// students' info export from E-Kreta
+    #[arg(long, env = "BACKBOARD_STUDENTS_FILE")]
+    students: Option<String>,
+    /// url of the Blueboard server
+    #[arg(long, env = "BACKBOARD_BLUEBOARD_URL")]
+    blueboard_url: String,
+    #[arg(long, env = "BACKBOARD_IMPORT_KEY", hide_env_values = true)]
+    import_key: String,
+    /// name of the import key, recorded in the import history
+    #[arg(long, env = "BACKBOARD_IMPORT_KEY_NAME")]
+    import_key_name: Option<String>,
+    /// uploaded before the import if provided
+    #[arg(long, env = "BACKBOARD_RESET_KEY_PASSWORD", hide_env_values = true)]
+    reset_key_password: Option<String>,
+    /// write the per-user outcome here, as json if it ends with `.json`, csv otherwise
+    #[arg(long, env = "BACKBOARD_REPORT_FILE")]
+    report: Option<String>,
+    /// SQLite database recording every import run
+    #[arg(long, env = "BACKBOARD_HISTORY_FILE", default_value = history::HISTORY_PATH)]
+    history: String,
+    /// json or toml profile mapping the columns of the exports, the built-in one if not given
+    #[arg(long, env = "BACKBOARD_MAPPING_PROFILE")]
+    mapping_profile: Option<String>,
+    /// skip the users already imported by an interrupted run with the same inputs
+    #[arg(long)]
+    resume: bool,
+    /// import the valid rows even if some rows of the input files are invalid
+    #[arg(long)]
+    skip_invalid_rows: bool,
+    /// skip the users whose data hasn't changed since the last import
+    #[arg(long)]
+    changes_only: bool,
+    /// import only the grades of these classes (comma-separated or repeated)
+    #[arg(long, value_delimiter = ',')]
+    include_class: Vec<String>,
+    /// leave out the grades of these classes
+    #[arg(long, value_delimiter = ',')]
+    exclude_class: Vec<String>,
+    /// import only the grades of these subjects
+    #[arg(long, value_delimiter = ',')]
+    include_subject: Vec<String>,
+    /// leave out the grades of these subjects
+    #[arg(long, value_delimiter = ',')]
+    exclude_subject: Vec<String>,
+    /// import only the grades of these subject categories
+    #[arg(long, value_delimiter = ',')]
+    include_subject_category: Vec<String>,
+    /// leave out the grades of these subject categories
+    #[arg(long, value_delimiter = ',')]
+    exclude_subject_category: Vec<String>,
+    /// import only the grades created on or after this day, like `2025-09-01`
+    #[arg(long)]
+    since: Option<NaiveDate>,
+    /// import only the grades created on or before this day, like `2025-12-31`
+    #[arg(long)]
+    until: Option<NaiveDate>,
+    /// the server's pepper of the keyed om code hashes, users are matched by the legacy hashes only without it
+    #[arg(long, env = "BACKBOARD_OM_CODE_PEPPER", hide_env_values = true)]
+    om_code_pepper: Option<String>,
+    /// log names, classes, grades and keys unredacted, only for debugging on a machine the logs never leave
+    #[arg(long, env = backboard::redact::UNSAFE_DEBUG_ENV)]
+    unsafe_debug: bool,
+    /// only print what would be uploaded (as json), don't encrypt or send anything
+    #[arg(long)]
+    dry_run: bool,
+    /// only push the names and classes of `--students` to the matched users, without any grades\
+    /// the server keeps only the latest import of a user until they log in, so the users that have been posted grades
+    /// from here are left out, as their pending grades would be lost, see `--overwrite-pending-grades`
+    #[arg(long, requires = "students", conflicts_with_all = ["grades", "dry_run"])]
+    roster_only: bool,
+    /// with `--roster-only`, sync the users that have been posted grades as well,
+    /// discarding the grades of the ones who haven't logged in since
+    #[arg(long, requires = "roster_only")]
+    overwrite_pending_grades: bool,
+    /// only list the students of `--students` without a registered user (to `--report` if given), nothing is uploaded
+    #[arg(long, requires = "students", conflicts_with_all = ["grades", "dry_run", "roster_only"])]
+    coverage: bool,
+}
+
+fn exit_code(err: &ImportError) -> u8 {
+    match err {
+        err if err.is_unauthorized() => 3,
+        ImportError::Input(_) | ImportError::InvalidRows(_) => 4,
+        ImportError::Response(_) | ImportError::Request(_) => 5,
+        ImportError::Io(_) => 1,
+    }
+}
+
+fn print_event(event: ImportEvent) {
+    match event {
+        ImportEvent::Fetching(num_users) => println!("fetching users: {num_users}"),
+        ImportEvent::InputFormat(detected) => {
+            println!("{:?} file: {}", detected.file, detected.format);
+        }
+        ImportEvent::Users(num_users) => println!("users: {num_users}"),
+        ImportEvent::Progress(percent) => println!("progress: {percent}%"),
+        ImportEvent::Summary(summary) => println!(
+            "processed {} of {} user(s){}",
+            summary.processed,
+            summary.total,
+            if summary.cancelled { ", cancelled" } else { "" }
+        ),
+    }
+}
+
+/// cancelled on `Ctrl+C`
+fn cancel_on_ctrl_c() -> CancellationToken {
+    let cancel = CancellationToken::new();
+    tokio::spawn({
+        let cancel = cancel.clone();
+        async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                eprintln!("cancelling, waiting for the in-flight uploads to finish");
+                cancel.cancel();
+            }
+        }
+    });
+    cancel
+}
+
+/// write the per-user outcome to `report_path` if given, then exit according to it
+fn finish(import_report: &ImportReport, report_path: Option<String>, cancelled: bool) -> ExitCode {
+    if let Some(path) = report_path
+        && let Err(err) = import_report.export(&path)
+    {
+        eprintln!("couldn't write report to {path:?}: {err}");
+        return ExitCode::FAILURE;
+    }
+
+    let num_failed = import_report.num_failed();
+    if num_failed > 0 {
+        eprintln!(
+            "the data of {num_failed} of {} user(s) couldn't be imported",
+            import_report.results.len()
+        );
+        return ExitCode::from(6);
+    }
+    if cancelled {
+        return ExitCode::from(130);
+    }
+    println!("done");
+    ExitCode::SUCCESS
+}
+
+fn roster_options(args: &Args) -> RosterOptions {
+    RosterOptions {
+        students_file_path: args.students.clone().expect("required by clap"),
+        blueboard_url: args.blueboard_url.clone(),
+        import_key: args.import_key.clone(),
+        skip_invalid_rows: args.skip_invalid_rows,
+        mapping_profile_path: args.mapping_profile.clone(),
+        om_code_pepper: args.om_code_pepper.clone(),
+        overwrite_pending_grades: args.overwrite_pending_grades,
+        fingerprints_path: None,
+    }
+}
+
+/// `--coverage`, see [`importer::check_coverage`]
+async fn check_coverage(args: Args) -> ExitCode {
+    let coverage = match importer::check_coverage(roster_options(&args), print_event).await {
+        Ok(coverage) => coverage,
+        Err(err) => {
+            eprintln!("coverage check failed: {err}");
+            return ExitCode::from(exit_code(&err));
+        }
+    };
+    println!(
+        "{} of {} student(s) registered",
+        coverage.num_registered, coverage.num_students
+    );
+    match args.report {
+        Some(path) => {
+            if let Err(err) = coverage.export(&path) {
+                eprintln!("couldn't write report to {path:?}: {err}");
+                return ExitCode::FAILURE;
+            }
+        }
+        None => {
+            for student in &coverage.unregistered {
+                println!("{} ({})", student.student_name, student.school_class);
+            }
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+/// `--roster-only`, see [`importer::sync_roster`]
+async fn sync_roster(args: Args) -> ExitCode {
+    let options = roster_options(&args);
+    let cancel = cancel_on_ctrl_c();
+    match importer::sync_roster(options, &cancel, print_event).await {
+        Ok(roster_report) => finish(&roster_report, args.report, cancel.is_cancelled()),
+        Err(err) => {
+            eprintln!("roster sync failed: {err}");
+            ExitCode::from(exit_code(&err))
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = Args::parse();
+    backboard::init_logger(args.unsafe_debug, Some(Path::new(backboard::LOG_DIR)));
+    if args.roster_only {
+        return sync_roster(args).await;
+    }
+    if args.coverage {
+        return check_coverage(args).await;
+    }
+
+    let options = ImportOptions {
+        grades_file_paths: args.grades,
+        students_file_path: args.students,
+        blueboard_url: args.blueboard_url,
+        update_reset_key_password: args.reset_key_password.is_some(),
+        reset_key_password: args.reset_key_password.unwrap_or_default(),
+        import_key: args.import_key,
+        resume: args.resume,
+        skip_invalid_rows: args.skip_invalid_rows,
+        mapping_profile_path: args.mapping_profile,
+        changes_only: args.changes_only,
+        filter: GradeFilter {
+            include_classes: args.include_class,
+            exclude_classes: args.exclude_class,
+            include_subjects: args.include_subject,
+            exclude_subjects: args.exclude_subject,
+            include_subject_categories: args.include_subject_category,
+            exclude_subject_categories: args.exclude_subject_category,
+            since: args.since,
+            until: args.until,
+        },
+        import_key_name: args.import_key_name,
+        om_code_pepper: args.om_code_pepper,
+        history_path: Some(args.history.into()),
+        journal_path: None,
+        fingerprints_path: None,
+    };
+    match importer::validate_input(&options) {
+        Ok(validation) => {
+            for issue in &validation.grades {
+                eprintln!("grades: {issue}");
+            }
+            for issue in &validation.students {
+                eprintln!("students: {issue}");
+            }
+        }
+        Err(err) => {
+            eprintln!("couldn't read the input files: {err}");
+            return ExitCode::from(exit_code(&err));
+        }
+    }
+    if args.dry_run {
+        return match importer::preview_import(options, print_event).await {
+            Ok(preview) => {
+                let preview =
+                    serde_json::to_string_pretty(&preview).expect("preview is serializable");
+                println!("{preview}");
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!("preview failed: {err}");
+                ExitCode::from(exit_code(&err))
+            }
+        };
+    }
+
+    if !options.resume
+        && let Ok(Some(num_done)) = importer::find_resumable(&options)
+    {
+        println!(
+            "an interrupted import with the same inputs already did {num_done} user(s), pass `--resume` to skip them"
+        );
+    }
+
+    let cancel = cancel_on_ctrl_c();
+    match importer::import_grades(options, &cancel, print_event).await {
+        Ok(import_report) => finish(&import_report, args.report, cancel.is_cancelled()),
+        Err(err) => {
+            eprintln!("import failed: {err}");
+            ExitCode::from(exit_code(&err))
+        }
+    }
+}
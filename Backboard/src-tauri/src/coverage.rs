@@ -0,0 +1,112 @@
+//! # Coverage
+//! which students of the students export haven't registered on LovassyApp yet\
+//! the om codes are hashed locally and compared to the hashed ones of the server, so no om code leaves the machine
+
+use crate::grades_processor::BackboardStudent;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// a student of the students export without a registered user
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnregisteredStudent {
+    pub student_name: String,
+    pub school_class: String,
+}
+
+/// how many of the students have registered, see [`check_coverage`](crate::importer::check_coverage)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverageReport {
+    /// in the students export
+    pub num_students: usize,
+    /// of those, registered on the server
+    pub num_registered: usize,
+    /// sorted by class, then name
+    pub unregistered: Vec<UnregisteredStudent>,
+}
+impl CoverageReport {
+    /// diff the hashed-om-code mapped `students` against the hashed om codes of the `registered` users
+    pub fn new<'a>(
+        students: &HashMap<String, BackboardStudent>,
+        registered: impl IntoIterator<Item = &'a str>,
+    ) -> Self {
+        let registered = registered.into_iter().collect::<HashSet<_>>();
+        let mut unregistered = students
+            .iter()
+            .filter(|(hashed_om, _)| !registered.contains(hashed_om.as_str()))
+            .map(|(_, student)| UnregisteredStudent {
+                student_name: student.name.clone(),
+                school_class: student.class.clone(),
+            })
+            .collect::<Vec<_>>();
+        unregistered.sort_by(|a, b| {
+            (&a.school_class, &a.student_name).cmp(&(&b.school_class, &b.student_name))
+        });
+        log::info!(
+            "{} of {} student(s) unregistered",
+            unregistered.len(),
+            students.len()
+        );
+        Self {
+            num_students: students.len(),
+            num_registered: students.len() - unregistered.len(),
+            unregistered,
+        }
+    }
+
+    /// write the unregistered students to `path` as csv, using the ';' character as delimiter, like E-Kreta does
+    /// # Errors
+    /// can't write to `path`
+    pub fn write_csv(&self, path: impl AsRef<Path>) -> Result<(), csv::Error> {
+        let mut writer = csv::WriterBuilder::new().delimiter(b';').from_path(path)?;
+        for student in &self.unregistered {
+            writer.serialize(student)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// write the report to `path` as pretty json
+    /// # Errors
+    /// can't write to `path`
+    pub fn write_json(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    /// write the report to `path`, as json if it has a `.json` extension, as csv otherwise
+    /// # Errors
+    /// can't write to `path`
+    pub fn export(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        log::info!("exporting coverage report to {path:?}");
+        if path.extension().is_some_and(|ext| ext == "json") {
+            self.write_json(path).map_err(|e| e.to_string())
+        } else {
+            self.write_csv(path).map_err(|e| e.to_string())
+        }
+    }
+}
+
+#[test]
+fn unregistered_students() {
+    let students = crate::grades_processor::fixtures::students();
+    let registered = students.keys().next().unwrap();
+
+    let nobody = CoverageReport::new(&students, []);
+    assert_eq!(nobody.num_registered, 0);
+    assert_eq!(nobody.unregistered.len(), students.len());
+
+    let coverage = CoverageReport::new(&students, [registered.as_str(), "someone else"]);
+    assert_eq!(coverage.num_students, students.len());
+    assert_eq!(coverage.num_registered, 1);
+    assert!(coverage.unregistered.is_empty());
+
+    let path = std::env::temp_dir().join("backboard-test-coverage.csv");
+    nobody.export(&path).unwrap();
+    let csv = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(csv, "studentName;schoolClass\nBuga Matyi;9.A\n");
+}
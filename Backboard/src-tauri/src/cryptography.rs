@@ -1,50 +1,519 @@
 //! # Cryptography
 //! contains code to (post-quantum) securely hash and encrypt sensitive user data
 
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use argon2::{Argon2, Params};
 use base64::{Engine, engine::general_purpose};
 use crypto_hash::{Algorithm, Hasher};
+use hmac::{Hmac, Mac};
 use libaes::Cipher;
 use pbkdf2::pbkdf2_hmac_array;
 use rand::random;
-use safe_pqc_kyber::{KyberError, encapsulate};
-use sha2::Sha512;
+use safe_pqc_kyber::{KyberError, decapsulate, encapsulate, keypair};
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Sha512};
+use std::fmt;
 use std::io::Write;
+use std::str::FromStr;
 
-pub fn generate_salt() -> String {
-    general_purpose::STANDARD.encode(random::<[u8; 16]>())
+/// prefix of the [`EnvelopeVersion::V2`] envelopes, the [`EnvelopeVersion::V1`] ones have none
+pub const ENVELOPE_V2_PREFIX: &str = "v2:";
+
+/// prefix of the [`EnvelopeVersion::V3`] envelopes
+pub const ENVELOPE_V3_PREFIX: &str = "v3:";
+
+/// format of the data encrypted by [`kyber_encrypt`], the server has to be able to decrypt it\
+/// the server's version doesn't tell which ones it can, so only [`Self::V1`] is used unless asked otherwise
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EnvelopeVersion {
+    /// `encapsulation|salt|iv;ciphertext`, AES-256-CBC, tampering goes unnoticed
+    #[default]
+    V1,
+    /// `v2:encapsulation|salt|nonce;ciphertext`, AES-256-GCM, the tag of the ciphertext covers the header as well
+    V2,
+    /// `v3:kdf|encapsulation|salt|nonce;ciphertext`, like [`Self::V2`], but the key is derived with the [`KdfParams`]
+    /// given in the header instead of the [legacy](KdfParams::LEGACY) ones
+    V3,
+}
+impl fmt::Display for EnvelopeVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::V1 => write!(f, "v1"),
+            Self::V2 => write!(f, "v2"),
+            Self::V3 => write!(f, "v3"),
+        }
+    }
+}
+impl FromStr for EnvelopeVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "v1" | "1" => Ok(Self::V1),
+            "v2" | "2" => Ok(Self::V2),
+            "v3" | "3" => Ok(Self::V3),
+            other => Err(format!(
+                "unknown envelope version {other:?}, expected v1, v2 or v3"
+            )),
+        }
+    }
 }
 
-pub fn generate_basic_key(data: &str, salt: &str) -> String {
-    let encryption_key = pbkdf2_hmac_array::<Sha512, 32>(data.as_bytes(), salt.as_bytes(), 1000);
-    general_purpose::STANDARD.encode(encryption_key)
+/// key derivation function of [`generate_basic_key`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KdfAlgorithm {
+    Pbkdf2Sha512,
+    /// with a parallelism of 1
+    Argon2id,
 }
 
-pub fn aes_encrypt(data: &str, key: String) -> String {
+/// how [`generate_basic_key`] derives the key, written into the header of the [`EnvelopeVersion::V3`] envelopes
+/// like `argon2id$m=19456,t=2,s=16` or `pbkdf2-sha512$i=210000,s=16`, see [`FromStr`] and [`Display`](fmt::Display)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct KdfParams {
+    pub algorithm: KdfAlgorithm,
+    /// of PBKDF2, or the passes of Argon2id
+    pub iterations: u32,
+    /// KiB of memory used by Argon2id, 0 for PBKDF2
+    pub memory_kib: u32,
+    /// random bytes of the salt, before base64 encoding
+    pub salt_len: usize,
+}
+impl KdfParams {
+    /// what the [`EnvelopeVersion::V1`] and [`EnvelopeVersion::V2`] envelopes are derived with
+    pub const LEGACY: Self = Self {
+        algorithm: KdfAlgorithm::Pbkdf2Sha512,
+        iterations: 1000,
+        memory_kib: 0,
+        salt_len: 16,
+    };
+
+    /// the least OWASP recommends for PBKDF2-HMAC-SHA512
+    pub const PBKDF2: Self = Self {
+        algorithm: KdfAlgorithm::Pbkdf2Sha512,
+        iterations: 210_000,
+        memory_kib: 0,
+        salt_len: 16,
+    };
+
+    /// the 19 MiB, 2 passes OWASP recommends for Argon2id
+    pub const ARGON2ID: Self = Self {
+        algorithm: KdfAlgorithm::Argon2id,
+        iterations: 2,
+        memory_kib: 19 * 1024,
+        salt_len: 16,
+    };
+
+    /// the most memory Argon2id may use, 256 MiB
+    pub const MAX_MEMORY_KIB: u32 = 256 * 1024;
+    /// the most passes of Argon2id
+    pub const MAX_PASSES: u32 = 64;
+    /// the most iterations of PBKDF2
+    pub const MAX_ITERATIONS: u32 = 10_000_000;
+    /// the longest salt, in bytes
+    pub const MAX_SALT_LEN: usize = 64;
+
+    /// the parameters are within the limits above, so an [`EnvelopeVersion::V3`] header can't make
+    /// deriving a key take forever or run out of memory
+    /// # Errors
+    /// [`CryptoError::KdfLimits`]
+    pub fn check_limits(&self) -> Result<(), CryptoError> {
+        let within = match self.algorithm {
+            KdfAlgorithm::Pbkdf2Sha512 => self.iterations <= Self::MAX_ITERATIONS,
+            KdfAlgorithm::Argon2id => {
+                self.iterations <= Self::MAX_PASSES && self.memory_kib <= Self::MAX_MEMORY_KIB
+            }
+        };
+        if within && self.salt_len <= Self::MAX_SALT_LEN {
+            Ok(())
+        } else {
+            Err(CryptoError::KdfLimits(*self))
+        }
+    }
+
+    /// like [`FromStr`], without checking the [limits](Self::check_limits)
+    fn parse_unchecked(s: &str) -> Result<Self, String> {
+        let (algorithm, params) = s.trim().split_once('$').unwrap_or((s.trim(), ""));
+        let (algorithm, defaults) = match algorithm.to_lowercase().as_str() {
+            "pbkdf2-sha512" | "pbkdf2" => (KdfAlgorithm::Pbkdf2Sha512, Self::PBKDF2),
+            "argon2id" => (KdfAlgorithm::Argon2id, Self::ARGON2ID),
+            other => {
+                return Err(format!(
+                    "unknown kdf {other:?}, expected pbkdf2-sha512 or argon2id"
+                ));
+            }
+        };
+        let mut kdf = defaults;
+        for param in params.split(',').filter(|p| !p.is_empty()) {
+            let (name, value) = param
+                .split_once('=')
+                .ok_or_else(|| format!("kdf parameter {param:?} has no value"))?;
+            let invalid = |_| format!("invalid kdf parameter {param:?}");
+            match (algorithm, name.trim()) {
+                (_, "s") => kdf.salt_len = value.parse().map_err(invalid)?,
+                (KdfAlgorithm::Pbkdf2Sha512, "i") | (KdfAlgorithm::Argon2id, "t") => {
+                    kdf.iterations = value.parse().map_err(invalid)?;
+                }
+                (KdfAlgorithm::Argon2id, "m") => kdf.memory_kib = value.parse().map_err(invalid)?,
+                _ => return Err(format!("unknown kdf parameter {param:?}")),
+            }
+        }
+        if kdf.iterations == 0 || kdf.salt_len < 8 {
+            return Err(String::from(
+                "the kdf needs at least 1 iteration and 8 bytes of salt",
+            ));
+        }
+        if algorithm == KdfAlgorithm::Argon2id {
+            kdf.argon2().map_err(|e| e.to_string())?;
+        }
+        Ok(kdf)
+    }
+
+    /// the Argon2id parameters
+    /// # Errors
+    /// too little memory or no passes
+    fn argon2(&self) -> Result<Argon2<'static>, CryptoError> {
+        let params = Params::new(self.memory_kib, self.iterations, 1, Some(32))?;
+        Ok(Argon2::new(
+            argon2::Algorithm::Argon2id,
+            argon2::Version::V0x13,
+            params,
+        ))
+    }
+}
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self::ARGON2ID
+    }
+}
+impl fmt::Display for KdfParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.algorithm {
+            KdfAlgorithm::Pbkdf2Sha512 => {
+                write!(f, "pbkdf2-sha512$i={},s={}", self.iterations, self.salt_len)
+            }
+            KdfAlgorithm::Argon2id => write!(
+                f,
+                "argon2id$m={},t={},s={}",
+                self.memory_kib, self.iterations, self.salt_len
+            ),
+        }
+    }
+}
+impl FromStr for KdfParams {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let kdf = Self::parse_unchecked(s)?;
+        kdf.check_limits().map_err(|e| e.to_string())?;
+        Ok(kdf)
+    }
+}
+impl TryFrom<String> for KdfParams {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+impl From<KdfParams> for String {
+    fn from(kdf: KdfParams) -> Self {
+        kdf.to_string()
+    }
+}
+
+/// `len` random bytes, base64 encoded
+pub fn generate_salt(len: usize) -> String {
+    let salt = (0..len).map(|_| random::<u8>()).collect::<Vec<_>>();
+    general_purpose::STANDARD.encode(salt)
+}
+
+/// derive a 32 byte key from `data` and the (base64 encoded) `salt`, as set by `kdf`
+/// # Errors
+/// invalid Argon2id parameters, or ones beyond the [limits](KdfParams::check_limits)
+pub fn generate_basic_key(data: &str, salt: &str, kdf: &KdfParams) -> Result<String, CryptoError> {
+    kdf.check_limits()?;
+    let encryption_key = match kdf.algorithm {
+        KdfAlgorithm::Pbkdf2Sha512 => {
+            pbkdf2_hmac_array::<Sha512, 32>(data.as_bytes(), salt.as_bytes(), kdf.iterations)
+        }
+        KdfAlgorithm::Argon2id => {
+            let mut key = [0; 32];
+            kdf.argon2()?
+                .hash_password_into(data.as_bytes(), salt.as_bytes(), &mut key)?;
+            key
+        }
+    };
+    Ok(general_purpose::STANDARD.encode(encryption_key))
+}
+
+/// the data couldn't be encrypted or decrypted, typically due to a corrupt key
+#[derive(Debug)]
+pub enum CryptoError {
+    /// `what` isn't valid base64
+    Base64 {
+        what: &'static str,
+        error: base64::DecodeError,
+    },
+    /// `what` isn't `expected` bytes long
+    KeyLength {
+        what: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+    /// encapsulation or decapsulation failed, e.g. the public key is of another Kyber variant
+    Kyber(KyberError),
+    /// the data to be encrypted couldn't be turned into json
+    Serialization(serde_json::Error),
+    /// the encrypted data misses `what`
+    MalformedEnvelope { what: &'static str },
+    /// wrong key, or tampered or truncated ciphertext
+    Decryption,
+    /// the key couldn't be derived with the [`KdfParams`]
+    Kdf(argon2::Error),
+    /// the [`KdfParams`] are beyond the [limits](KdfParams::check_limits)
+    KdfLimits(KdfParams),
+}
+impl CryptoError {
+    /// short, stable name of the variant, for the [report](crate::report::UserOutcome::EncryptionError)
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Base64 { .. } => "base64",
+            Self::KeyLength { .. } => "keyLength",
+            Self::Kyber(_) => "kyber",
+            Self::Serialization(_) => "serialization",
+            Self::MalformedEnvelope { .. } => "malformedEnvelope",
+            Self::Decryption => "decryption",
+            Self::Kdf(_) => "kdf",
+            Self::KdfLimits(_) => "kdfLimits",
+        }
+    }
+}
+impl fmt::Display for CryptoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Base64 { what, error } => write!(f, "invalid base64 {what}: {error}"),
+            Self::KeyLength {
+                what,
+                expected,
+                actual,
+            } => write!(f, "{what} is {actual} bytes instead of {expected}"),
+            Self::Kyber(err) => write!(f, "kyber: {err}"),
+            Self::Serialization(err) => write!(f, "couldn't serialize data: {err}"),
+            Self::MalformedEnvelope { what } => write!(f, "encrypted data has no {what}"),
+            Self::Decryption => write!(f, "wrong key, or tampered or truncated ciphertext"),
+            Self::Kdf(err) => write!(f, "couldn't derive the key: {err}"),
+            Self::KdfLimits(kdf) => write!(f, "kdf parameters beyond the limits: {kdf}"),
+        }
+    }
+}
+impl std::error::Error for CryptoError {}
+impl From<KyberError> for CryptoError {
+    fn from(e: KyberError) -> Self {
+        Self::Kyber(e)
+    }
+}
+impl From<serde_json::Error> for CryptoError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Serialization(e)
+    }
+}
+impl From<argon2::Error> for CryptoError {
+    fn from(e: argon2::Error) -> Self {
+        Self::Kdf(e)
+    }
+}
+
+/// AES-256-CBC with a random iv\
+/// `base64(iv);base64(ciphertext)`
+/// # Errors
+/// `key` isn't 32 base64 encoded bytes
+pub fn aes_encrypt(data: &str, key: String) -> Result<String, CryptoError> {
     let iv = random::<[u8; 16]>();
-    let cipher = Cipher::new_256(
-        &general_purpose::STANDARD
-            .decode(key)
-            .unwrap()
-            .try_into()
-            .unwrap(),
-    );
+    let cipher = Cipher::new_256(&decode_array(&key, "key")?);
     let encrypted_data = cipher.cbc_encrypt(&iv, data.as_bytes());
-    general_purpose::STANDARD.encode(iv) + ";" + &general_purpose::STANDARD.encode(&encrypted_data)
+    Ok(general_purpose::STANDARD.encode(iv)
+        + ";"
+        + &general_purpose::STANDARD.encode(&encrypted_data))
 }
 
-pub fn kyber_encrypt(data: &str, public_key: String) -> Result<String, KyberError> {
-    let public_key_bytes = general_purpose::STANDARD.decode(public_key).unwrap();
+/// AES-256-GCM, `aad` is authenticated but not encrypted\
+/// `base64(nonce);base64(ciphertext and tag)`
+/// # Errors
+/// `key` isn't 32 base64 encoded bytes
+pub fn aes_gcm_encrypt(data: &str, key: String, aad: &[u8]) -> Result<String, CryptoError> {
+    let nonce = random::<[u8; 12]>();
+    let cipher = Aes256Gcm::new(&decode_array::<32>(&key, "key")?.into());
+    let payload = Payload {
+        msg: data.as_bytes(),
+        aad,
+    };
+    let encrypted_data = cipher
+        .encrypt(&Nonce::from(nonce), payload)
+        .expect("the data fits into a single message");
+    Ok(general_purpose::STANDARD.encode(nonce)
+        + ";"
+        + &general_purpose::STANDARD.encode(encrypted_data))
+}
+
+/// encrypt `data` with a key encapsulated for the `public_key`, in the envelope of `version`\
+/// the key is derived with `kdf` in [`EnvelopeVersion::V3`] envelopes, with the [legacy](KdfParams::LEGACY) parameters otherwise
+/// # Errors
+/// `public_key` isn't base64, or not a Kyber768 public key, invalid `kdf`
+pub fn kyber_encrypt(
+    data: &str,
+    public_key: String,
+    version: EnvelopeVersion,
+    kdf: &KdfParams,
+) -> Result<String, CryptoError> {
+    let public_key_bytes = decode(&public_key, "public key")?;
 
     let mut rng = rand::thread_rng();
 
     let (encapsulation, secret) = encapsulate(&public_key_bytes, &mut rng)?;
 
-    let salt = generate_salt();
-    let encryption_key = generate_basic_key(&general_purpose::STANDARD.encode(secret), &salt);
+    let kdf = match version {
+        EnvelopeVersion::V1 | EnvelopeVersion::V2 => &KdfParams::LEGACY,
+        EnvelopeVersion::V3 => kdf,
+    };
+    let salt = generate_salt(kdf.salt_len);
+    let encryption_key = generate_basic_key(&general_purpose::STANDARD.encode(secret), &salt, kdf)?;
+
+    let header = general_purpose::STANDARD.encode(encapsulation) + "|" + &salt + "|";
+    let header = match version {
+        EnvelopeVersion::V1 => return Ok(header + &aes_encrypt(data, encryption_key)?),
+        EnvelopeVersion::V2 => String::from(ENVELOPE_V2_PREFIX) + &header,
+        EnvelopeVersion::V3 => format!("{ENVELOPE_V3_PREFIX}{kdf}|{header}"),
+    };
+    let encrypted_data = aes_gcm_encrypt(data, encryption_key, header.as_bytes())?;
+    Ok(header + &encrypted_data)
+}
+
+/// a Kyber768 keypair, base64 encoded like the users' public keys coming from the server
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KyberKeypair {
+    pub public_key: String,
+    pub private_key: String,
+}
+impl KyberKeypair {
+    /// a fresh, random keypair
+    pub fn generate() -> Self {
+        let keys = keypair(&mut rand::thread_rng());
+        Self {
+            public_key: general_purpose::STANDARD.encode(keys.public),
+            private_key: general_purpose::STANDARD.encode(keys.secret),
+        }
+    }
+}
+
+/// inverse of [`aes_encrypt`]
+/// # Errors
+/// malformed `data` or `key`, wrong `key`
+pub fn aes_decrypt(data: &str, key: String) -> Result<String, CryptoError> {
+    let (iv, encrypted_data) = data
+        .split_once(';')
+        .ok_or(CryptoError::MalformedEnvelope { what: "iv" })?;
+    let iv = decode_array::<16>(iv, "iv")?;
+    let encrypted_data = decode(encrypted_data, "ciphertext")?;
+    // not made of whole blocks
+    if encrypted_data.is_empty() || encrypted_data.len() % 16 != 0 {
+        return Err(CryptoError::Decryption);
+    }
+    let mut cipher = Cipher::new_256(&decode_array(&key, "key")?);
+    // libaes drops as many bytes as the last one says, without checking them
+    cipher.set_auto_padding(false);
+    let mut decrypted_data = cipher.cbc_decrypt(&iv, &encrypted_data);
+    // PKCS7, a wrong key leaves garbage instead
+    let padding = decrypted_data.last().copied().unwrap_or_default();
+    let len = decrypted_data.len().saturating_sub(usize::from(padding));
+    if !(1..=16).contains(&padding) || decrypted_data[len..].iter().any(|&b| b != padding) {
+        return Err(CryptoError::Decryption);
+    }
+    decrypted_data.truncate(len);
+    String::from_utf8(decrypted_data).map_err(|_| CryptoError::Decryption)
+}
+
+/// inverse of [`aes_gcm_encrypt`], with the same `aad`
+/// # Errors
+/// malformed `data` or `key`, wrong `key` or `aad`, tampered ciphertext
+pub fn aes_gcm_decrypt(data: &str, key: String, aad: &[u8]) -> Result<String, CryptoError> {
+    let (nonce, encrypted_data) = data
+        .split_once(';')
+        .ok_or(CryptoError::MalformedEnvelope { what: "nonce" })?;
+    let nonce = decode_array::<12>(nonce, "nonce")?;
+    let cipher = Aes256Gcm::new(&decode_array::<32>(&key, "key")?.into());
+    let payload = Payload {
+        msg: &decode(encrypted_data, "ciphertext")?,
+        aad,
+    };
+    let decrypted_data = cipher
+        .decrypt(&Nonce::from(nonce), payload)
+        .map_err(|_| CryptoError::Decryption)?;
+    String::from_utf8(decrypted_data).map_err(|_| CryptoError::Decryption)
+}
+
+/// inverse of [`kyber_encrypt`], of any [envelope version](EnvelopeVersion), to test the envelopes this crate makes\
+/// it's written after the server's `KyberKeypair.Decrypt`, but isn't checked against it
+/// # Errors
+/// malformed `data` or `private_key`, wrong `private_key`, tampered [`EnvelopeVersion::V2`] or [`EnvelopeVersion::V3`] envelope
+pub fn kyber_decrypt(data: &str, private_key: String) -> Result<String, CryptoError> {
+    let (version, kdf, envelope) = if let Some(envelope) = data.strip_prefix(ENVELOPE_V3_PREFIX) {
+        let (kdf, envelope) = envelope
+            .split_once('|')
+            .ok_or(CryptoError::MalformedEnvelope { what: "kdf" })?;
+        let kdf = KdfParams::parse_unchecked(kdf)
+            .map_err(|_| CryptoError::MalformedEnvelope { what: "valid kdf" })?;
+        kdf.check_limits()?;
+        (EnvelopeVersion::V3, kdf, envelope)
+    } else if let Some(envelope) = data.strip_prefix(ENVELOPE_V2_PREFIX) {
+        (EnvelopeVersion::V2, KdfParams::LEGACY, envelope)
+    } else {
+        (EnvelopeVersion::V1, KdfParams::LEGACY, data)
+    };
+    let mut parts = envelope.splitn(3, '|');
+    let (Some(encapsulation), Some(salt), Some(encrypted_data)) =
+        (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(CryptoError::MalformedEnvelope {
+            what: "encapsulation or salt",
+        });
+    };
 
-    let encrypted_data = aes_encrypt(data, encryption_key.clone());
+    let secret = decapsulate(
+        &decode(encapsulation, "encapsulation")?,
+        &decode(&private_key, "private key")?,
+    )?;
+    let encryption_key = generate_basic_key(&general_purpose::STANDARD.encode(secret), salt, &kdf)?;
+
+    match version {
+        EnvelopeVersion::V1 => aes_decrypt(encrypted_data, encryption_key),
+        EnvelopeVersion::V2 | EnvelopeVersion::V3 => {
+            let header = &data[..data.len() - encrypted_data.len()];
+            aes_gcm_decrypt(encrypted_data, encryption_key, header.as_bytes())
+        }
+    }
+}
 
-    Ok(general_purpose::STANDARD.encode(encapsulation) + "|" + &salt + "|" + &encrypted_data)
+/// base64 decoded `data`, `what` names it in the error
+fn decode(data: &str, what: &'static str) -> Result<Vec<u8>, CryptoError> {
+    general_purpose::STANDARD
+        .decode(data)
+        .map_err(|error| CryptoError::Base64 { what, error })
+}
+
+/// base64 decoded `data` of exactly `N` bytes, `what` names it in the error
+fn decode_array<const N: usize>(data: &str, what: &'static str) -> Result<[u8; N], CryptoError> {
+    decode(data, what)?
+        .try_into()
+        .map_err(|bytes: Vec<u8>| CryptoError::KeyLength {
+            what,
+            expected: N,
+            actual: bytes.len(),
+        })
 }
 
 pub fn hash(data: &str) -> String {
@@ -54,3 +523,175 @@ pub fn hash(data: &str) -> String {
 
     general_purpose::STANDARD.encode(hash_result)
 }
+
+/// HMAC-SHA256 of `data` keyed with the server's `pepper`, base64 encoded like [`hash`]\
+/// unlike the bare [`hash`] of an om code, it can't be brute-forced without the `pepper`
+pub fn keyed_hash(data: &str, pepper: &str) -> String {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(pepper.as_bytes())
+        .expect("hmac takes keys of any length");
+    mac.update(data.as_bytes());
+    general_purpose::STANDARD.encode(mac.finalize().into_bytes())
+}
+
+/// how the om codes are hashed: the legacy [`hash`], and the [`keyed_hash`] as well if the server's pepper is known\
+/// while the users migrate, the server may have either of them, see [`OmHashing::hash`]
+#[derive(Debug, Clone, Default)]
+pub struct OmHashing {
+    pub pepper: Option<String>,
+}
+impl OmHashing {
+    /// keyed with `pepper` as well, unless it's missing or empty
+    pub fn new(pepper: Option<String>) -> Self {
+        Self {
+            pepper: pepper.filter(|pepper| !pepper.is_empty()),
+        }
+    }
+
+    /// the legacy hash of `om_code`, and its keyed one if there's a pepper
+    pub fn hash(&self, om_code: &str) -> (String, Option<String>) {
+        let keyed = self
+            .pepper
+            .as_deref()
+            .map(|pepper| keyed_hash(om_code, pepper));
+        (hash(om_code), keyed)
+    }
+}
+
+#[test]
+fn envelope_versions() {
+    assert_eq!(EnvelopeVersion::default(), EnvelopeVersion::V1);
+    assert_eq!("V2".parse(), Ok(EnvelopeVersion::V2));
+
+    let public_key = KyberKeypair::generate().public_key;
+    let kdf = KdfParams::default();
+    let v1 = kyber_encrypt("{}", public_key.clone(), EnvelopeVersion::V1, &kdf).unwrap();
+    assert_eq!(v1.split('|').count(), 3);
+    let v2 = kyber_encrypt("{}", public_key.clone(), EnvelopeVersion::V2, &kdf).unwrap();
+    let (header, encrypted_data) = v2.rsplit_once('|').unwrap();
+    assert!(header.starts_with(ENVELOPE_V2_PREFIX));
+    let (nonce, ciphertext) = encrypted_data.split_once(';').unwrap();
+    assert_eq!(general_purpose::STANDARD.decode(nonce).unwrap().len(), 12);
+    // 2 bytes of data and a 16 byte tag
+    assert_eq!(
+        general_purpose::STANDARD.decode(ciphertext).unwrap().len(),
+        18
+    );
+    let v3 = kyber_encrypt("{}", public_key, EnvelopeVersion::V3, &KdfParams::PBKDF2).unwrap();
+    assert!(v3.starts_with("v3:pbkdf2-sha512$i=210000,s=16|"));
+}
+
+#[test]
+fn decrypt_envelopes() {
+    let keys = KyberKeypair::generate();
+    let kdf = KdfParams::default();
+    for version in [
+        EnvelopeVersion::V1,
+        EnvelopeVersion::V2,
+        EnvelopeVersion::V3,
+    ] {
+        let encrypted =
+            kyber_encrypt("{\"Grades\":[]}", keys.public_key.clone(), version, &kdf).unwrap();
+        assert_eq!(
+            kyber_decrypt(&encrypted, keys.private_key.clone()).unwrap(),
+            "{\"Grades\":[]}"
+        );
+    }
+
+    let encrypted =
+        kyber_encrypt("{}", keys.public_key.clone(), EnvelopeVersion::V2, &kdf).unwrap();
+    let (header, encrypted_data) = encrypted.rsplit_once('|').unwrap();
+    let other_salt = header.rsplit_once('|').unwrap().0.to_owned() + "|" + &generate_salt(16);
+    let tampered = other_salt + "|" + encrypted_data;
+    assert!(matches!(
+        kyber_decrypt(&tampered, keys.private_key.clone()),
+        Err(CryptoError::Decryption)
+    ));
+    let truncated = &encrypted[..encrypted.len() - 4];
+    assert!(kyber_decrypt(truncated, keys.private_key.clone()).is_err());
+
+    // the kdf parameters are authenticated too
+    let encrypted =
+        kyber_encrypt("{}", keys.public_key.clone(), EnvelopeVersion::V3, &kdf).unwrap();
+    let weaker = encrypted.replacen("t=2", "t=1", 1);
+    assert!(matches!(
+        kyber_decrypt(&weaker, keys.private_key),
+        Err(CryptoError::Decryption)
+    ));
+
+    // the padding is checked, a wrong key of a v1 envelope doesn't decrypt to some string either
+    for _ in 0..100 {
+        let encrypted = aes_encrypt("{\"Grades\":[]}", generate_salt(32)).unwrap();
+        assert!(matches!(
+            aes_decrypt(&encrypted, generate_salt(32)),
+            Err(CryptoError::Decryption)
+        ));
+    }
+
+    let corrupt_key = kyber_encrypt("{}", String::from("not base64!"), EnvelopeVersion::V1, &kdf);
+    assert_eq!(corrupt_key.unwrap_err().kind(), "base64");
+    let short_key = kyber_encrypt("{}", generate_salt(16), EnvelopeVersion::V1, &kdf);
+    assert_eq!(short_key.unwrap_err().kind(), "kyber");
+    let short_aes_key = aes_encrypt("{}", generate_salt(16));
+    assert_eq!(short_aes_key.unwrap_err().kind(), "keyLength");
+}
+
+#[test]
+fn kdf_params() {
+    for kdf in [KdfParams::LEGACY, KdfParams::PBKDF2, KdfParams::ARGON2ID] {
+        assert_eq!(kdf.to_string().parse(), Ok(kdf));
+    }
+    assert_eq!("argon2id".parse(), Ok(KdfParams::ARGON2ID));
+    assert_eq!(
+        "pbkdf2$i=600000".parse::<KdfParams>().unwrap().iterations,
+        600_000
+    );
+    assert!("argon2id$m=4,t=2".parse::<KdfParams>().is_err());
+    assert!("argon2id$i=2".parse::<KdfParams>().is_err());
+    assert!("pbkdf2$s=4".parse::<KdfParams>().is_err());
+    assert!("scrypt".parse::<KdfParams>().is_err());
+    for beyond in [
+        "argon2id$m=1048576",
+        "argon2id$t=65",
+        "pbkdf2$i=10000001",
+        "pbkdf2$s=65",
+    ] {
+        assert!(beyond.parse::<KdfParams>().is_err(), "{beyond}");
+    }
+    let header = "argon2id$m=4194304,t=2,s=16";
+    assert!(matches!(
+        kyber_decrypt(
+            &format!("{ENVELOPE_V3_PREFIX}{header}|a|b|c;d"),
+            String::new()
+        ),
+        Err(CryptoError::KdfLimits(_))
+    ));
+    // the legacy key can't change, the server derives it the same way
+    assert_eq!(
+        generate_basic_key("secret", "salt", &KdfParams::LEGACY).unwrap(),
+        general_purpose::STANDARD.encode(pbkdf2_hmac_array::<Sha512, 32>(b"secret", b"salt", 1000))
+    );
+
+    let kdf = KdfParams::default();
+    let salt = generate_salt(kdf.salt_len);
+    let key = generate_basic_key("secret", &salt, &kdf).unwrap();
+    assert_eq!(decode(&key, "key").unwrap().len(), 32);
+    assert_ne!(
+        key,
+        generate_basic_key("secret", &salt, &KdfParams::LEGACY).unwrap()
+    );
+}
+
+#[test]
+fn kdf_derivation_time() {
+    // a key is derived for every student, so an import of a whole school can't take too long
+    const MAX_DERIVATION_TIME: std::time::Duration = std::time::Duration::from_millis(500);
+    let kdf = KdfParams::default();
+    let salt = generate_salt(kdf.salt_len);
+    let start = std::time::Instant::now();
+    generate_basic_key("secret", &salt, &kdf).unwrap();
+    let elapsed = start.elapsed();
+    assert!(
+        elapsed < MAX_DERIVATION_TIME,
+        "deriving with {kdf} took {elapsed:?}"
+    );
+}
@@ -0,0 +1,146 @@
+//! # Filter
+//! narrows an import down to some classes, subjects or dates, e.g. to re-send 11.B's grades after fixing a teacher's mistake\
+//! applied to the parsed [grades](Grade), before anything gets encrypted\
+//! the server only keeps the latest import of a user until they log in, so a filtered import replaces a full one
+//! still pending, the grades left out of it only arrive with the next full import
+
+use crate::grade::Grade;
+use crate::grades_processor::BackboardStudent;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// which grades to import, an empty include list means everything, values are compared case-insensitively
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GradeFilter {
+    pub include_classes: Vec<String>,
+    pub exclude_classes: Vec<String>,
+    pub include_subjects: Vec<String>,
+    pub exclude_subjects: Vec<String>,
+    pub include_subject_categories: Vec<String>,
+    pub exclude_subject_categories: Vec<String>,
+    /// "Bejegyzés dátuma" on or after this
+    pub since: Option<NaiveDate>,
+    /// "Bejegyzés dátuma" on or before this
+    pub until: Option<NaiveDate>,
+}
+
+/// what a [`GradeFilter`] selected
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterSummary {
+    pub selected_grades: usize,
+    pub left_out_grades: usize,
+    /// of the selected grades, sorted
+    pub classes: Vec<String>,
+    pub subjects: Vec<String>,
+}
+
+/// `value` passes the `include` and `exclude` lists, a missing one only if nothing has to be included
+fn passes(include: &[String], exclude: &[String], value: Option<&str>) -> bool {
+    let Some(value) = value.map(|v| v.trim().to_lowercase()) else {
+        return include.is_empty();
+    };
+    let listed = |list: &[String]| list.iter().any(|v| v.trim().to_lowercase() == value);
+    (include.is_empty() || listed(include)) && !listed(exclude)
+}
+
+impl GradeFilter {
+    /// lets everything through
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// the `grade` of a student of `school_class` is selected
+    pub fn matches(&self, grade: &Grade, school_class: Option<&str>) -> bool {
+        passes(&self.include_classes, &self.exclude_classes, school_class)
+            && passes(
+                &self.include_subjects,
+                &self.exclude_subjects,
+                Some(&grade.subject),
+            )
+            && passes(
+                &self.include_subject_categories,
+                &self.exclude_subject_categories,
+                Some(&grade.subject_category),
+            )
+            && self.since.is_none_or(|since| grade.create_date >= since)
+            && self.until.is_none_or(|until| grade.create_date <= until)
+    }
+
+    /// keep only the selected ones of the hashed-om-code mapped `grades`, students left without any are removed\
+    /// the class of a student is taken from the `students`' data if found there, from the grades otherwise
+    pub fn apply(
+        &self,
+        grades: &mut HashMap<String, Vec<Grade>>,
+        students: &HashMap<String, BackboardStudent>,
+    ) -> FilterSummary {
+        let mut summary = FilterSummary::default();
+        let mut classes = BTreeSet::new();
+        let mut subjects = BTreeSet::new();
+        grades.retain(|hashed_om, grades| {
+            let student_class = students.get(hashed_om).map(|s| s.class.as_str());
+            let len = grades.len();
+            grades.retain(|grade| {
+                let school_class = student_class.or(grade.school_class.as_deref());
+                let selected = self.matches(grade, school_class);
+                if selected {
+                    classes.extend(school_class.map(str::to_owned));
+                    subjects.insert(grade.subject.clone());
+                }
+                selected
+            });
+            summary.selected_grades += grades.len();
+            summary.left_out_grades += len - grades.len();
+            !grades.is_empty()
+        });
+        summary.classes = classes.into_iter().collect();
+        summary.subjects = subjects.into_iter().collect();
+        log::info!(
+            "filter selected {} grade(s), left out {}",
+            summary.selected_grades,
+            summary.left_out_grades
+        );
+        summary
+    }
+}
+
+#[test]
+fn filter_grades() {
+    let grades = crate::grades_processor::fixtures::grades();
+    let num_grades = grades.values().map(Vec::len).sum::<usize>();
+
+    let mut all = grades.clone();
+    let summary = GradeFilter::default().apply(&mut all, &HashMap::new());
+    assert_eq!(
+        (summary.selected_grades, summary.left_out_grades),
+        (num_grades, 0)
+    );
+    assert_eq!(all, grades);
+
+    let mut none = grades.clone();
+    let only_other_class = GradeFilter {
+        include_classes: vec![String::from("11.B")],
+        ..Default::default()
+    };
+    let summary = only_other_class.apply(&mut none, &HashMap::new());
+    assert!(none.is_empty());
+    assert_eq!(summary.left_out_grades, num_grades);
+
+    let grade = &grades.values().next().unwrap()[0];
+    let filter = GradeFilter {
+        include_classes: vec![String::from(" 10.c ")],
+        exclude_subjects: vec![String::from("Matematika")],
+        since: grade.create_date.pred_opt(),
+        until: Some(grade.create_date),
+        ..Default::default()
+    };
+    assert!(filter.matches(grade, Some("10.C")));
+    assert!(!filter.matches(grade, None));
+    let exclude_subject = GradeFilter {
+        exclude_subjects: vec![grade.subject.to_uppercase()],
+        ..Default::default()
+    };
+    assert!(!exclude_subject.matches(grade, Some("10.C")));
+}
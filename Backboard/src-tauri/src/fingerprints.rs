@@ -0,0 +1,144 @@
+//! # Fingerprints
+//! digests of the grade collections last posted to each user, so a "changes only" import can skip the users
+//! whose data hasn't changed since\
+//! only hashed om codes and sha256 digests are kept, nothing sensitive, per server url
+
+use crate::grades_processor::GradeCollection;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// where the fingerprints are kept by default, in the working dir, the GUI keeps them in the app data dir instead
+pub const FINGERPRINTS_PATH: &str = ".lovassyapp-backboard.fingerprints.json";
+
+/// digest of the normalised contents of the `collection`, the order of the grades doesn't matter\
+/// the user's id and public key are part of it, so re-registered users get their data again
+pub fn fingerprint(collection: &GradeCollection) -> String {
+    let mut grades = collection
+        .grades
+        .iter()
+        .map(|grade| serde_json::to_string(grade).expect("grades are serializable"))
+        .collect::<Vec<_>>();
+    grades.sort_unstable();
+
+    let mut hasher = Sha256::new();
+    let user = &collection.user;
+    for field in [
+        user.id(),
+        user.public_key(),
+        &collection.student_name,
+        collection.school_class.as_deref().unwrap_or_default(),
+    ] {
+        hasher.update(field);
+        hasher.update(b"\0");
+    }
+    for grade in grades {
+        hasher.update(grade);
+        hasher.update(b"\0");
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// fingerprints of the collections posted to the server at `blueboard_url`, by hashed om code
+#[derive(Debug)]
+pub struct Fingerprints {
+    path: PathBuf,
+    blueboard_url: String,
+    /// of every server, by url
+    servers: HashMap<String, HashMap<String, String>>,
+}
+impl Fingerprints {
+    /// load the fingerprints at `path` of the server at `blueboard_url`, none if there's no such file yet\
+    /// an unreadable or invalid file is started over, at worst every user gets their data again
+    pub fn load(path: impl Into<PathBuf>, blueboard_url: &str) -> Self {
+        let path = path.into();
+        let servers = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                log::warn!("invalid fingerprints at {path:?}, starting over: {e}");
+                HashMap::new()
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => {
+                log::warn!("couldn't read the fingerprints at {path:?}, starting over: {e}");
+                HashMap::new()
+            }
+        };
+        Self {
+            path,
+            blueboard_url: blueboard_url.to_owned(),
+            servers,
+        }
+    }
+
+    /// the very same data was posted to the user with `hashed_om` the last time
+    pub fn is_unchanged(&self, hashed_om: &str, fingerprint: &str) -> bool {
+        self.servers
+            .get(&self.blueboard_url)
+            .and_then(|digests| digests.get(hashed_om))
+            .is_some_and(|last| last == fingerprint)
+    }
+
+    /// note that the data with `fingerprint` has been posted to the user with `hashed_om`
+    pub fn record(&mut self, hashed_om: String, fingerprint: String) {
+        self.servers
+            .entry(self.blueboard_url.clone())
+            .or_default()
+            .insert(hashed_om, fingerprint);
+    }
+
+    /// grades have been posted to the user with `hashed_om`, which they may not have received yet
+    pub fn has_posted(&self, hashed_om: &str) -> bool {
+        self.servers
+            .get(&self.blueboard_url)
+            .is_some_and(|digests| digests.contains_key(hashed_om))
+    }
+
+    /// forget what was posted to the user with `hashed_om`, as a [roster sync](crate::importer::sync_roster) replaced it
+    pub fn forget(&mut self, hashed_om: &str) {
+        if let Some(digests) = self.servers.get_mut(&self.blueboard_url) {
+            digests.remove(hashed_om);
+        }
+    }
+
+    /// write the fingerprints back to their file
+    /// # Errors
+    /// can't write the file
+    pub fn save(&self) -> io::Result<()> {
+        fs::write(&self.path, serde_json::to_vec(&self.servers)?)
+    }
+}
+
+#[test]
+fn skip_unchanged_collections() {
+    use crate::grades_processor::fixtures;
+
+    let mut collection = fixtures::collection(fixtures::user("key"));
+    let hashed_om = collection.user.om_code_hashed().to_owned();
+    let digest = fingerprint(&collection);
+    collection.grades.reverse();
+    assert_eq!(fingerprint(&collection), digest);
+
+    let path = std::env::temp_dir().join("backboard-test-fingerprints.json");
+    let _ = fs::remove_file(&path);
+    let mut fingerprints = Fingerprints::load(&path, "url");
+    assert!(!fingerprints.is_unchanged(&hashed_om, &digest));
+    fingerprints.record(hashed_om.clone(), digest.clone());
+    fingerprints.save().unwrap();
+
+    let fingerprints = Fingerprints::load(&path, "url");
+    assert!(fingerprints.is_unchanged(&hashed_om, &digest));
+    collection.student_name = String::from("Buga Matyi");
+    assert!(!fingerprints.is_unchanged(&hashed_om, &fingerprint(&collection)));
+    let other_server = Fingerprints::load(&path, "other url");
+    assert!(!other_server.is_unchanged(&hashed_om, &digest));
+    assert!(!other_server.has_posted(&hashed_om));
+
+    // a roster sync replaced the posted grades
+    let mut fingerprints = fingerprints;
+    assert!(fingerprints.has_posted(&hashed_om));
+    fingerprints.forget(&hashed_om);
+    assert!(!fingerprints.has_posted(&hashed_om));
+    assert!(!fingerprints.is_unchanged(&hashed_om, &digest));
+}
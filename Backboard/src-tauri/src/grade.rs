@@ -0,0 +1,388 @@
+//! # Grade
+//! typed model of a [parsed grade](BackboardGrade), normalised on the client,
+//! so bad data is caught before upload instead of crashing the server's `BackboardUtils`\
+//! it's serialized from the export's very strings, the way they used to be sent, as the server hashes `Grade.Uid` from them
+
+use crate::grades_processor::BackboardGrade;
+use crate::validator::parse_date;
+use chrono::NaiveDate;
+use serde::{Serialize, Serializer};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// "Értékelés módja" of the grades counting twice
+const DOUBLE_WEIGHT_TYPE: &str = "Írásbeli témazáró dolgozat (dupla súllyal)";
+
+/// what a grade is about, the server tells them apart the same way
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GradeKind {
+    Regular,
+    /// "Magatartás" is filled
+    Behaviour,
+    /// "Szorgalom" is filled
+    Diligence,
+}
+
+/// value of a grade
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(tag = "kind", content = "value", rename_all = "camelCase")]
+pub enum GradeValue {
+    /// "Jegy", from 1 to 5
+    Numeric(u8),
+    /// no "Jegy", only a textual evaluation in "Osztályzat"
+    TextOnly,
+}
+
+/// a value of a [`BackboardGrade`] that can't be normalised
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GradeError {
+    /// "Bejegyzés dátuma" or "Rögzítés dátuma" isn't like `2025.09.03.`
+    InvalidDate(String),
+    /// "Jegy" isn't a whole number from 1 to 5
+    InvalidGrade(String),
+}
+impl fmt::Display for GradeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidDate(date) => write!(f, "invalid date: {date:?}"),
+            Self::InvalidGrade(grade) => write!(f, "grade outside 1-5: {grade:?}"),
+        }
+    }
+}
+impl std::error::Error for GradeError {}
+
+/// E-Kreta's placeholder for empty cells
+const PLACEHOLDER: &str = " - ";
+
+/// values of the textual grades without a "Jegy", as the server looks them up
+const TEXT_GRADES: [(&str, u8); 5] = [
+    ("peldas", 5),
+    ("jo", 4),
+    ("valtozo", 3),
+    ("rossz", 2),
+    ("hanyag", 1),
+];
+
+/// `None` for empty cells and E-Kreta's placeholder
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|v| !matches!(v.trim(), "" | "-"))
+}
+
+/// a grade, the way the server's `BackboardGrade` expects it: the strings of the export as they are,
+/// a single changed character would change its `Grade.Uid` and duplicate the grade on the next import\
+/// a missing "Jegy" is sent as `null` though, as the server parses every other value as a number,
+/// and empty "Magatartás" and "Szorgalom" cells as the placeholder, the server tells the [`GradeKind`] by that
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct WireGrade {
+    student_name: String,
+    subject_category: String,
+    subject: String,
+    group: String,
+    teacher: Option<String>,
+    theme: String,
+    r#type: Option<String>,
+    text_grade: String,
+    grade: Option<String>,
+    short_text_grade: String,
+    behavior_grade: String,
+    diligence_grade: String,
+    create_date: String,
+    record_date: String,
+}
+impl From<&BackboardGrade> for WireGrade {
+    fn from(raw: &BackboardGrade) -> Self {
+        Self {
+            student_name: raw.student_name.clone(),
+            subject_category: raw.subject_category.clone(),
+            subject: raw.subject.clone(),
+            group: raw.group.clone(),
+            teacher: raw.teacher.clone(),
+            theme: raw.theme.clone(),
+            r#type: raw.r#type.clone(),
+            text_grade: raw.text_grade.clone(),
+            grade: non_empty(raw.grade.clone()),
+            short_text_grade: raw.short_text_grade.clone(),
+            behavior_grade: non_empty(Some(raw.behavior_grade.clone()))
+                .unwrap_or_else(|| PLACEHOLDER.to_owned()),
+            diligence_grade: non_empty(Some(raw.diligence_grade.clone()))
+                .unwrap_or_else(|| PLACEHOLDER.to_owned()),
+            create_date: raw.create_date.clone(),
+            record_date: raw.record_date.clone(),
+        }
+    }
+}
+
+/// a grade of a student, normalised\
+/// serialized as the strings it was parsed from, the way the server's `BackboardGrade` expects it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grade {
+    pub student_name: String,
+    pub school_class: Option<String>,
+    pub subject_category: String,
+    pub subject: String,
+    pub group: String,
+    pub teacher: Option<String>,
+    /// `None` if the grade has no theme
+    pub theme: Option<String>,
+    /// "Értékelés módja"
+    pub evaluation_type: Option<String>,
+    /// in percent, `200` for the grades counting twice
+    pub weight: u16,
+    pub kind: GradeKind,
+    pub value: GradeValue,
+    /// "Osztályzat", like `Jeles(5)`
+    pub text_grade: String,
+    /// "Szöveges értékelés"
+    pub short_text_grade: Option<String>,
+    pub behaviour_grade: Option<String>,
+    pub diligence_grade: Option<String>,
+    /// "Bejegyzés dátuma"
+    pub create_date: NaiveDate,
+    /// "Rögzítés dátuma"
+    pub record_date: NaiveDate,
+    wire: WireGrade,
+}
+impl TryFrom<BackboardGrade> for Grade {
+    type Error = GradeError;
+
+    fn try_from(raw: BackboardGrade) -> Result<Self, Self::Error> {
+        let wire = WireGrade::from(&raw);
+        let date = |value: String| parse_date(&value).ok_or(GradeError::InvalidDate(value));
+        let value = match non_empty(raw.grade) {
+            None => GradeValue::TextOnly,
+            Some(grade) => match grade.trim().parse() {
+                Ok(value @ 1..=5) => GradeValue::Numeric(value),
+                _ => return Err(GradeError::InvalidGrade(grade)),
+            },
+        };
+        let behaviour_grade = non_empty(Some(raw.behavior_grade));
+        let diligence_grade = non_empty(Some(raw.diligence_grade));
+        let kind = if behaviour_grade.is_some() {
+            GradeKind::Behaviour
+        } else if diligence_grade.is_some() {
+            GradeKind::Diligence
+        } else {
+            GradeKind::Regular
+        };
+        let evaluation_type = non_empty(raw.r#type);
+        Ok(Self {
+            weight: if evaluation_type.as_deref() == Some(DOUBLE_WEIGHT_TYPE) {
+                200
+            } else {
+                100
+            },
+            student_name: raw.student_name,
+            school_class: non_empty(raw.school_class),
+            subject_category: raw.subject_category,
+            subject: raw.subject,
+            group: raw.group,
+            teacher: non_empty(raw.teacher),
+            theme: non_empty(Some(raw.theme)),
+            evaluation_type,
+            kind,
+            value,
+            text_grade: raw.text_grade,
+            short_text_grade: non_empty(Some(raw.short_text_grade)),
+            behaviour_grade,
+            diligence_grade,
+            create_date: date(raw.create_date)?,
+            record_date: date(raw.record_date)?,
+            wire,
+        })
+    }
+}
+
+/// what the server's `Grade.Uid` is hashed from, grades with the same identity are duplicates\
+/// the values are normalised the way `BackboardUtils` does, before hashing them
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct GradeIdentity<'a> {
+    create_date: NaiveDate,
+    record_date: NaiveDate,
+    subject: String,
+    subject_category: String,
+    theme: String,
+    teacher: &'a str,
+    evaluation_type: &'a str,
+    kind: GradeKind,
+    group: &'a str,
+    weight: u16,
+    text_grade: &'a str,
+    short_text_grade: &'a str,
+    value: u8,
+    student_name: &'a str,
+}
+impl Grade {
+    pub fn identity(&self) -> GradeIdentity<'_> {
+        let wire = &self.wire;
+        GradeIdentity {
+            create_date: self.create_date,
+            record_date: self.record_date,
+            subject: capitalise(&wire.subject),
+            subject_category: capitalise(&wire.subject_category),
+            theme: if wire.theme == PLACEHOLDER {
+                "Névtelen jegy".to_owned()
+            } else {
+                capitalise(&wire.theme)
+            },
+            teacher: wire.teacher.as_deref().unwrap_or("Névtelen hős"),
+            evaluation_type: wire.r#type.as_deref().unwrap_or("Nincs leírás"),
+            kind: self.kind,
+            group: &wire.group,
+            weight: self.weight,
+            text_grade: without_value(match self.kind {
+                GradeKind::Behaviour => &wire.behavior_grade,
+                GradeKind::Diligence => &wire.diligence_grade,
+                GradeKind::Regular => &wire.text_grade,
+            }),
+            short_text_grade: without_value(&wire.short_text_grade),
+            value: match self.value {
+                GradeValue::Numeric(value) => value,
+                GradeValue::TextOnly => {
+                    let text_grade = wire.text_grade.to_lowercase();
+                    TEXT_GRADES
+                        .iter()
+                        .find(|(text, _)| *text == text_grade)
+                        .map_or(0, |(_, value)| *value)
+                }
+            },
+            student_name: &wire.student_name,
+        }
+    }
+}
+
+/// the first character in upper case, like the server's `char.ToUpper(s[0]) + s.Substring(1)`
+fn capitalise(value: &str) -> String {
+    let mut chars = value.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
+}
+
+/// a textual grade without its value in parentheses, like `Jeles` of `Jeles(5)`
+fn without_value(text_grade: &str) -> &str {
+    text_grade.split('(').next().unwrap_or_default()
+}
+
+/// merge the hashed-om-code mapped grades of several exports, overlapping ones included\
+/// duplicates(by [`Grade::identity`]) are left out, keeping the first one, their number is returned as well
+pub fn merge_grades(
+    exports: impl IntoIterator<Item = HashMap<String, Vec<Grade>>>,
+) -> (HashMap<String, Vec<Grade>>, usize) {
+    let mut merged: HashMap<String, Vec<Grade>> = HashMap::new();
+    for export in exports {
+        for (hashed_om, grades) in export {
+            merged.entry(hashed_om).or_default().extend(grades);
+        }
+    }
+    let mut num_duplicates = 0;
+    for grades in merged.values_mut() {
+        let mut seen = HashSet::new();
+        let keep = grades
+            .iter()
+            .map(|grade| seen.insert(grade.identity()))
+            .collect::<Vec<_>>();
+        let mut keep = keep.into_iter();
+        let len = grades.len();
+        grades.retain(|_| keep.next().unwrap_or_default());
+        num_duplicates += len - grades.len();
+    }
+    if num_duplicates > 0 {
+        log::info!("left out {num_duplicates} duplicate grade(s)");
+    }
+    (merged, num_duplicates)
+}
+
+impl Serialize for Grade {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.wire.serialize(serializer)
+    }
+}
+
+#[test]
+fn normalise_grades() {
+    let grades = crate::grades_processor::fixtures::grades();
+    let grade = &grades.values().next().unwrap()[0];
+    assert_eq!(grade.value, GradeValue::Numeric(5));
+    assert_eq!(grade.kind, GradeKind::Regular);
+    assert_eq!(grade.weight, 100);
+    assert_eq!(
+        grade.create_date,
+        NaiveDate::from_ymd_opt(2025, 9, 3).unwrap()
+    );
+    // the same as the export's strings
+    assert_eq!(
+        serde_json::to_string(grade).unwrap(),
+        r#"{"StudentName":"Aladar Barnabas","SubjectCategory":"Biológia","Subject":"biológia","Group":"10.C","Teacher":"Makacs Tímea","Theme":"Ismétlő feladatok; otszor","Type":"Órai munka","TextGrade":"Jeles(5)","Grade":"5","ShortTextGrade":" - ","BehaviorGrade":" - ","DiligenceGrade":" - ","CreateDate":"2025.09.03.","RecordDate":"2025.09.03."}"#
+    );
+}
+
+#[test]
+fn keep_wire_strings() {
+    // empty cells and E-Kreta's placeholder are normalised, but sent as they were,
+    // except for a missing "Jegy", the server only takes `null` for that,
+    // and empty "Magatartás" and "Szorgalom" cells, the server would take them for a behaviour grade
+    let grades = crate::grades_processor::fixtures::grades_of_rows(
+        "wire-strings",
+        &["Aladar Barnabas;10.C;2007.01.01;71624229433;Biológia;biológia;10.C; - ; - ; - ;Jeles(5); - ;;;;;2025.09.03;2025.09.03.;"],
+    )
+    .data;
+    let grade = &grades.values().next().unwrap()[0];
+    assert_eq!(
+        (grade.teacher.as_deref(), grade.theme.as_deref()),
+        (None, None)
+    );
+    assert_eq!(grade.value, GradeValue::TextOnly);
+    assert_eq!(grade.kind, GradeKind::Regular);
+    assert_eq!(
+        serde_json::to_string(grade).unwrap(),
+        r#"{"StudentName":"Aladar Barnabas","SubjectCategory":"Biológia","Subject":"biológia","Group":"10.C","Teacher":" - ","Theme":" - ","Type":" - ","TextGrade":"Jeles(5)","Grade":null,"ShortTextGrade":"","BehaviorGrade":" - ","DiligenceGrade":" - ","CreateDate":"2025.09.03","RecordDate":"2025.09.03."}"#
+    );
+}
+
+#[test]
+fn merge_overlapping_exports() {
+    use crate::grades_processor::fixtures::grades_of_rows;
+
+    let row = |theme: &str, date: &str| {
+        format!(
+            "Aladar Barnabas;10.C;2007.01.01;71624229433;Biológia;biológia;10.C;Makacs Tímea;{theme};Órai munka;\
+             Jeles(5);5; - ;; - ; - ;{date};{date};"
+        )
+    };
+    let day = "2025.09.03.";
+    // e.g. the exports of two overlapping periods
+    let first = grades_of_rows("merge-first", &[&row("a", day), &row("b", day)]).data;
+    let second = grades_of_rows("merge-second", &[&row("b", day), &row("c", day)]).data;
+    let (merged, num_duplicates) = merge_grades([first, second]);
+    assert_eq!(num_duplicates, 1);
+    let themes = merged
+        .values()
+        .flatten()
+        .map(|grade| grade.theme.as_deref())
+        .collect::<Vec<_>>();
+    assert_eq!(themes, [Some("a"), Some("b"), Some("c")]);
+
+    // only the same identity is a duplicate, not the same grade on another day
+    let other_day = grades_of_rows("merge-other-day", &[&row("a", "2025.09.04.")]).data;
+    let (merged, num_duplicates) = merge_grades([merged, other_day]);
+    assert_eq!(num_duplicates, 0);
+    assert_eq!(merged.values().map(Vec::len).sum::<usize>(), 4);
+}
+
+#[test]
+fn merge_by_server_identity() {
+    // the server capitalises the subject and the theme before hashing them
+    let grades = crate::grades_processor::fixtures::grades_of_rows(
+        "merge-case",
+        &[
+            "Aladar Barnabas;10.C;2007.01.01;71624229433;Biológia;biológia;10.C;Makacs Tímea;sejtek;Órai munka;Jeles(5);5; - ;; - ; - ;2025.09.03.;2025.09.03.;",
+            "Aladar Barnabas;10.C;2007.01.01;71624229433;biológia;Biológia;10.C;Makacs Tímea;Sejtek;Órai munka;Jeles(5);5; - ;; - ; - ;2025.09.03.;2025.09.03.;",
+        ],
+    )
+    .data;
+    let (merged, num_duplicates) = merge_grades([grades]);
+    assert_eq!(num_duplicates, 1);
+    assert_eq!(merged.values().next().unwrap()[0].subject, "biológia");
+}
@@ -2,115 +2,370 @@
 //! provides bindings and functions necessary to import grades and user(student) data\
 //! also processes imported data to match the format of the server, where it will be sent to
 
+use crate::cryptography::{CryptoError, EnvelopeVersion, KdfParams, OmHashing};
+use crate::grade::Grade;
+use crate::mapping::{Column, MappingProfile, ProfileError};
+use crate::redact::Redacted;
+use crate::sniffer::{self, CsvFormat};
+use crate::validator::{self, Row, RowIssue, RowProblem};
 use api::models::ImportIndexUsersResponse;
+use calamine::{Data, Reader, Xlsx, XlsxError, open_workbook};
+use csv::StringRecord;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
 
 /// # Backboard Grade
 /// bindings to parse a grade, that comes from an [E-Kreta](https://e-kreta.hu) export created by a school admin\
-/// **NOTE**: skipped fields on deserialization are: "Születési idő", "Utolsó mentés dátuma", "Százalékos értékelés"
-#[derive(Debug, Clone, Deserialize, Serialize)]
+/// the columns of the export are mapped to the fields by a [profile](crate::mapping::MappingProfile),
+/// then the raw strings get normalised into a [`Grade`]\
+/// **NOTE**: unmapped columns, like "Születési idő", "Utolsó mentés dátuma", "Százalékos értékelés" are skipped
+#[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct BackboardGrade {
-    #[serde(rename(deserialize = "Tanuló név"))]
     pub student_name: String,
-    #[serde(rename(deserialize = "Tanuló osztálya"), skip_serializing)]
     pub school_class: Option<String>,
-    #[serde(rename(deserialize = "Tanuló azonosítója"), skip_serializing)]
     om_code: String,
-    #[serde(rename(deserialize = "Tárgy kategória"))]
-    subject_category: String,
-    #[serde(rename(deserialize = "Tantárgy"))]
-    subject: String,
-    #[serde(rename(deserialize = "Osztály/Csoport név"))]
-    group: String,
-    #[serde(rename(deserialize = "Pedagógus név"), default)]
-    teacher: Option<String>,
-    #[serde(rename(deserialize = "Téma"))]
-    theme: String,
-    #[serde(rename(deserialize = "Értékelés módja"), default)]
-    r#type: Option<String>,
-    #[serde(rename(deserialize = "Osztályzat"))]
-    text_grade: String,
-    #[serde(rename(deserialize = "Jegy"), default)]
-    grade: Option<String>,
-    #[serde(rename(deserialize = "Szöveges értékelés"))]
-    short_text_grade: String,
-    #[serde(rename(deserialize = "Magatartás"))]
-    behavior_grade: String,
-    #[serde(rename(deserialize = "Szorgalom"))]
-    diligence_grade: String,
-    #[serde(rename(deserialize = "Bejegyzés dátuma"))]
-    create_date: String,
-    #[serde(rename(deserialize = "Rögzítés dátuma"))]
-    record_date: String,
+    pub subject_category: String,
+    pub subject: String,
+    pub group: String,
+    #[serde(default)]
+    pub teacher: Option<String>,
+    pub theme: String,
+    #[serde(default)]
+    pub r#type: Option<String>,
+    pub text_grade: String,
+    #[serde(default)]
+    pub grade: Option<String>,
+    pub short_text_grade: String,
+    pub behavior_grade: String,
+    pub diligence_grade: String,
+    pub create_date: String,
+    pub record_date: String,
 }
 impl BackboardGrade {
-    /// returns the hashed `om_code`, then replaces it with an empty string
-    pub fn hashed_om_code(&mut self) -> String {
-        crate::cryptography::hash(&std::mem::take(&mut self.om_code))
+    /// returns the [hashed](OmHashing::hash) `om_code`, then replaces it with an empty string
+    pub fn hashed_om_code(&mut self, hashing: &OmHashing) -> (String, Option<String>) {
+        hashing.hash(&std::mem::take(&mut self.om_code))
     }
 }
 
+/// a student from an E-Kreta export, the columns mapped by a [profile](crate::mapping::MappingProfile)
 #[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
 pub struct BackboardStudent {
-    #[serde(rename(deserialize = "Név"))]
     pub name: String,
-    #[serde(rename(deserialize = "Oktatási azonosítója"))]
     om_code: String,
-    #[serde(rename(deserialize = "Osztály"))]
     pub class: String,
 }
 impl BackboardStudent {
-    /// returns the hashed `om_code`, then replaces it with an empty string
-    pub fn hashed_om_code(&mut self) -> String {
-        crate::cryptography::hash(&std::mem::take(&mut self.om_code))
+    /// returns the [hashed](OmHashing::hash) `om_code`, then replaces it with an empty string
+    pub fn hashed_om_code(&mut self, hashing: &OmHashing) -> (String, Option<String>) {
+        hashing.hash(&std::mem::take(&mut self.om_code))
+    }
+}
+
+/// an input file couldn't be read or parsed
+#[derive(Debug)]
+pub enum InputError {
+    Io(std::io::Error),
+    Csv(csv::Error),
+    Xlsx(XlsxError),
+    Profile(ProfileError),
+    /// headers of required columns not found in the file, see [`MappingProfile`]
+    MissingColumns(Vec<String>),
+}
+impl std::fmt::Display for InputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Csv(err) => write!(f, "{err}"),
+            Self::Xlsx(err) => write!(f, "{err}"),
+            Self::Profile(err) => write!(f, "{err}"),
+            Self::MissingColumns(headers) => {
+                write!(f, "missing column(s): {}", headers.join(", "))
+            }
+        }
+    }
+}
+impl std::error::Error for InputError {}
+impl From<std::io::Error> for InputError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+impl From<csv::Error> for InputError {
+    fn from(e: csv::Error) -> Self {
+        Self::Csv(e)
+    }
+}
+impl From<ProfileError> for InputError {
+    fn from(e: ProfileError) -> Self {
+        Self::Profile(e)
+    }
+}
+impl From<XlsxError> for InputError {
+    fn from(e: XlsxError) -> Self {
+        Self::Xlsx(e)
+    }
+}
+
+/// text of an xlsx cell, as it'd be in the csv export: dates like `2025.09.03.`, whole numbers without decimals
+fn cell_to_string(cell: &Data) -> String {
+    match cell {
+        Data::DateTime(date) => date.as_datetime().map_or_else(
+            || date.to_string(),
+            |date| date.format("%Y.%m.%d.").to_string(),
+        ),
+        cell => cell.to_string(),
     }
 }
 
-/// reads, parses and processes a csv grades export from the `path`\
-/// a valid example can be found [here](../test_grades.csv)\
-/// **NOTE**: the csv shall use the ';' character as delimiter
+/// header and rows of a spreadsheet, each row with its line number
+type Rows = (StringRecord, Vec<(u64, StringRecord)>);
+
+/// reads the rows of the first worksheet of the xlsx at `path`,
+/// the first row being the header, just like in the csv export
+/// # Errors
+/// invalid xlsx or missing worksheet
+fn read_xlsx(path: &Path) -> Result<Rows, InputError> {
+    let mut workbook: Xlsx<_> = open_workbook(path)?;
+    let range = workbook
+        .worksheet_range_at(0)
+        .ok_or(XlsxError::WorksheetNotFound(String::from("#0")))??;
+    let first_line = range.start().map_or(0, |(row, _)| u64::from(row)) + 1;
+    let mut rows = (first_line..).zip(
+        range
+            .rows()
+            .map(|row| row.iter().map(cell_to_string).collect::<StringRecord>()),
+    );
+    let Some((_, headers)) = rows.next() else {
+        return Ok((StringRecord::new(), Vec::new()));
+    };
+    let rows = rows
+        .filter(|(_, row)| row.iter().any(|cell| !cell.trim().is_empty()))
+        .collect();
+    Ok((headers, rows))
+}
+
+/// reads the rows of the csv in `text`, separated by `delimiter`
 /// # Errors
 /// invalid csv
-pub fn process_grades_csv_file(
+fn read_csv(text: &str, delimiter: char) -> Result<Rows, InputError> {
+    let mut csv_raw = csv::ReaderBuilder::new()
+        .delimiter(delimiter as u8)
+        .flexible(true) // missing values are reported by the validation instead
+        .from_reader(text.as_bytes());
+    let headers = csv_raw.headers()?.clone();
+    let rows = csv_raw
+        .records()
+        .map(|record| {
+            let record = record?;
+            let line = record.position().map_or(0, csv::Position::line);
+            Ok((line, record))
+        })
+        .collect::<Result<_, csv::Error>>()?;
+    Ok((headers, rows))
+}
+
+/// how an input file was read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum InputFormat {
+    /// with the [sniffed](sniffer::sniff) delimiter and encoding
+    Csv(CsvFormat),
+    Xlsx,
+}
+impl std::fmt::Display for InputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Csv(CsvFormat {
+                delimiter,
+                encoding,
+            }) => write!(f, "csv, delimiter {delimiter:?}, {encoding}"),
+            Self::Xlsx => write!(f, "xlsx"),
+        }
+    }
+}
+
+/// data parsed from an input file, along with how it was read
+#[derive(Debug, Clone)]
+pub struct ParsedFile<T> {
+    pub data: T,
+    pub format: InputFormat,
+    /// problems of the rows left out of `data`
+    pub issues: Vec<RowIssue>,
+    /// the keyed hashes of the om codes, mapped to the legacy ones `data` is keyed by,
+    /// empty without a pepper, see [`OmHashing`]
+    pub keyed_hashes: HashMap<String, String>,
+}
+
+/// replace the headers of the mapped `columns` with the names of their fields
+/// # Errors
+/// a required column is missing
+fn map_headers(headers: &StringRecord, columns: &[Column]) -> Result<StringRecord, InputError> {
+    let missing = columns
+        .iter()
+        .filter(|c| c.required && !headers.iter().any(|h| h.trim() == c.header))
+        .map(|c| c.header.to_owned())
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        return Err(InputError::MissingColumns(missing));
+    }
+    Ok(headers
+        .iter()
+        .map(|h| {
+            columns
+                .iter()
+                .find(|c| h.trim() == c.header)
+                .map_or(h, |c| c.field)
+        })
+        .collect())
+}
+
+/// reads the rows of the spreadsheet at `path`, xlsx if it has a `.xlsx` extension, csv otherwise\
+/// the delimiter and encoding of the csv are [sniffed](sniffer::sniff), the headers mapped to fields by `columns`\
+/// rows that don't pass `validate` or can't be deserialized are left out, their [issues](RowIssue) collected,
+/// the rest is returned with their line numbers
+/// # Errors
+/// can't read `path`, invalid csv or xlsx, missing columns
+fn read_rows<T: DeserializeOwned>(
+    path: &Path,
+    columns: &[Column],
+    validate: fn(&Row) -> Vec<RowIssue>,
+) -> Result<ParsedFile<Vec<(u64, T)>>, InputError> {
+    let (format, (source_headers, rows)) = if path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("xlsx"))
+    {
+        (InputFormat::Xlsx, read_xlsx(path)?)
+    } else {
+        let (text, format) = sniffer::sniff(&std::fs::read(path)?);
+        (InputFormat::Csv(format), read_csv(&text, format.delimiter)?)
+    };
+    let headers = map_headers(&source_headers, columns)?;
+
+    let mut data = Vec::with_capacity(rows.len());
+    let mut issues = Vec::new();
+    for (line, record) in &rows {
+        let row = Row {
+            line: *line,
+            headers: &headers,
+            source_headers: &source_headers,
+            record,
+        };
+        let row_issues = validate(&row);
+        if !row_issues.is_empty() {
+            issues.extend(row_issues);
+            continue;
+        }
+        match record.deserialize(Some(&headers)) {
+            Ok(value) => data.push((*line, value)),
+            Err(e) => issues.push(match e.kind() {
+                csv::ErrorKind::Deserialize { err, .. } => {
+                    row.malformed(err.field(), err.kind().to_string())
+                }
+                _ => row.malformed(None, e.to_string()),
+            }),
+        }
+    }
+    if !issues.is_empty() {
+        log::warn!("{} problem(s) found in {path:?}", issues.len());
+    }
+
+    Ok(ParsedFile {
+        data,
+        format,
+        issues,
+        keyed_hashes: HashMap::new(),
+    })
+}
+
+/// reads, parses and processes a csv or xlsx grades export from the `path` into [`Grade`]s\
+/// the columns are mapped by the `profile`, invalid rows are left out, see [`validator::validate_grade`]\
+/// the om codes are hashed according to `hashing`\
+/// a valid example can be found [here](../test_grades.csv)
+/// # Errors
+/// can't read `path`, invalid csv or xlsx, missing columns
+pub fn process_grades_file(
     path: String,
-) -> Result<HashMap<String, Vec<BackboardGrade>>, csv::Error> {
+    profile: &MappingProfile,
+    hashing: &OmHashing,
+) -> Result<ParsedFile<HashMap<String, Vec<Grade>>>, InputError> {
     log::info!("processing grades from {path:?}");
-    let mut csv_raw = csv::ReaderBuilder::new().delimiter(b';').from_path(path)?;
-    let mut grades: HashMap<String, Vec<BackboardGrade>> = HashMap::new();
-    for grade in csv_raw.deserialize() {
-        let mut grade: BackboardGrade = grade?;
-        grades
-            .entry(grade.hashed_om_code())
-            .or_default()
-            .push(grade);
+    let parsed = read_rows::<BackboardGrade>(
+        path.as_ref(),
+        &profile.grades.columns(),
+        validator::validate_grade,
+    )?;
+    let mut issues = parsed.issues;
+    let mut grades: HashMap<String, Vec<Grade>> = HashMap::new();
+    let mut keyed_hashes = HashMap::new();
+    for (line, mut grade) in parsed.data {
+        let (hashed_om_code, keyed) = grade.hashed_om_code(hashing);
+        if let Some(keyed) = keyed {
+            keyed_hashes.insert(keyed, hashed_om_code.clone());
+        }
+        match Grade::try_from(grade) {
+            Ok(grade) => grades.entry(hashed_om_code).or_default().push(grade),
+            Err(e) => issues.push(RowIssue {
+                file: None,
+                line,
+                column: None,
+                value: None,
+                problem: RowProblem::Malformed {
+                    message: e.to_string(),
+                },
+            }),
+        }
     }
     log::info!("successfully processed grades");
-    log::trace!("hashed-om-id mapped grades: {grades:?}");
+    log::trace!("hashed-om-id mapped grades: {:?}", Redacted(&grades));
 
-    Ok(grades)
+    Ok(ParsedFile {
+        data: grades,
+        format: parsed.format,
+        issues,
+        keyed_hashes,
+    })
 }
 
-/// reads, parses and processes a csv student-info export from the `path`\
-/// a valid example can be found [here](../test_students.csv)\
-/// **NOTE**: the csv shall use the ';' character as delimiter
+/// reads, parses and processes a csv or xlsx student-info export from the `path`\
+/// the columns are mapped by the `profile`, invalid rows are left out, see [`validator::validate_student`]\
+/// the om codes are hashed according to `hashing`\
+/// a valid example can be found [here](../test_students.csv)
 /// # Errors
-/// invalid csv
-pub fn process_students_csv_file(
+/// can't read `path`, invalid csv or xlsx, missing columns
+pub fn process_students_file(
     path: String,
-) -> Result<HashMap<String, BackboardStudent>, csv::Error> {
+    profile: &MappingProfile,
+    hashing: &OmHashing,
+) -> Result<ParsedFile<HashMap<String, BackboardStudent>>, InputError> {
     log::info!("processing students from {path:?}");
-    let mut csv_raw = csv::ReaderBuilder::new().delimiter(b';').from_path(path)?;
+    let parsed = read_rows::<BackboardStudent>(
+        path.as_ref(),
+        &profile.students.columns(),
+        validator::validate_student,
+    )?;
     let mut students = HashMap::new();
-    for student in csv_raw.deserialize() {
-        let mut student: BackboardStudent = student?;
-        students.insert(student.hashed_om_code(), student);
+    let mut keyed_hashes = HashMap::new();
+    for (_, mut student) in parsed.data {
+        let (hashed_om_code, keyed) = student.hashed_om_code(hashing);
+        if let Some(keyed) = keyed {
+            keyed_hashes.insert(keyed, hashed_om_code.clone());
+        }
+        students.insert(hashed_om_code, student);
     }
     log::info!("successfully processed students");
-    log::trace!("hashed-om-id mapped students: {students:?}");
+    log::trace!("hashed-om-id mapped students: {:?}", Redacted(&students));
 
-    Ok(students)
+    Ok(ParsedFile {
+        data: students,
+        format: parsed.format,
+        issues: parsed.issues,
+        keyed_hashes,
+    })
 }
 
 /// data of a user(student) that comes from the server\
@@ -122,14 +377,51 @@ pub struct BackboardUser {
     public_key: String,
     om_code_hashed: String,
 }
+impl BackboardUser {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+    /// used to encrypt the data sent to the user
+    pub fn public_key(&self) -> &str {
+        &self.public_key
+    }
+    /// used as key to the imported data of the user
+    pub fn om_code_hashed(&self) -> &str {
+        &self.om_code_hashed
+    }
+}
 
-impl From<ImportIndexUsersResponse> for BackboardUser {
-    fn from(user: ImportIndexUsersResponse) -> Self {
-        BackboardUser {
-            id: user.id.unwrap().to_string(),
-            public_key: user.public_key.unwrap().unwrap(),
-            om_code_hashed: user.om_code_hashed.unwrap().unwrap(),
-        }
+/// a user record coming from the server is missing a field without which it can't be imported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserRecordError {
+    MissingId,
+    MissingPublicKey,
+    MissingOmCodeHashed,
+}
+impl std::fmt::Display for UserRecordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let field = match self {
+            Self::MissingId => "id",
+            Self::MissingPublicKey => "public key",
+            Self::MissingOmCodeHashed => "hashed om code",
+        };
+        write!(f, "user record from the server has no {field}")
+    }
+}
+impl std::error::Error for UserRecordError {}
+
+impl TryFrom<ImportIndexUsersResponse> for BackboardUser {
+    type Error = UserRecordError;
+
+    fn try_from(user: ImportIndexUsersResponse) -> Result<Self, Self::Error> {
+        // empty strings are just as useless as missing ones
+        let non_empty = |field: Option<Option<String>>| field.flatten().filter(|s| !s.is_empty());
+        Ok(BackboardUser {
+            id: user.id.ok_or(UserRecordError::MissingId)?.to_string(),
+            public_key: non_empty(user.public_key).ok_or(UserRecordError::MissingPublicKey)?,
+            om_code_hashed: non_empty(user.om_code_hashed)
+                .ok_or(UserRecordError::MissingOmCodeHashed)?,
+        })
     }
 }
 
@@ -138,20 +430,25 @@ impl From<ImportIndexUsersResponse> for BackboardUser {
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct GradeCollection {
-    pub grades: Vec<BackboardGrade>,
+    pub grades: Vec<Grade>,
     pub school_class: Option<String>,
     pub student_name: String,
     pub user: BackboardUser,
 }
 impl GradeCollection {
-    /// convert the [`GradeCollection`] to json and encrypt it to be safely transferred over the wire to the server
+    /// convert the [`GradeCollection`] to json and encrypt it to be safely transferred over the wire to the server,
+    /// in the envelope of `version`, derived with `kdf` if it's [`EnvelopeVersion::V3`]
     /// # Errors
-    /// coming from [`serde_json::to_string`] and [`crate::cryptography::kyber_encrypt`]
-    pub fn to_encrypted_json(&self, pub_key: String) -> Result<String, String> {
-        log::info!("encrypting user's grade collection");
-        let as_json = serde_json::to_string(&self).map_err(|e| e.to_string())?;
-        let ret =
-            crate::cryptography::kyber_encrypt(&as_json, pub_key).map_err(|e| e.to_string())?;
+    /// coming from [`serde_json::to_string`] and [`crate::cryptography::kyber_encrypt`], e.g. a corrupt `pub_key`
+    pub fn to_encrypted_json(
+        &self,
+        pub_key: String,
+        version: EnvelopeVersion,
+        kdf: &KdfParams,
+    ) -> Result<String, CryptoError> {
+        log::info!("encrypting user's grade collection ({version})");
+        let as_json = serde_json::to_string(&self)?;
+        let ret = crate::cryptography::kyber_encrypt(&as_json, pub_key, version, kdf)?;
         log::info!("successfully encrypted user's grade collection");
         Ok(ret)
     }
@@ -161,16 +458,200 @@ impl GradeCollection {
 fn parse_grades() {
     let path = String::from("test_grades.csv");
     assert!(std::fs::exists(&path).unwrap());
-    let grades = process_grades_csv_file(path).inspect_err(|err| eprintln!("{err}"));
+    let grades = process_grades_file(path, &MappingProfile::default(), &Default::default())
+        .inspect_err(|err| eprintln!("{err}"));
     assert!(grades.is_ok());
     eprintln!("imported {:#?}", grades.unwrap());
 }
 
+#[test]
+fn parse_xlsx_grades() {
+    let from_xlsx = process_grades_file(
+        String::from("test_grades.xlsx"),
+        &MappingProfile::default(),
+        &Default::default(),
+    )
+    .unwrap();
+    assert_eq!(from_xlsx.format, InputFormat::Xlsx);
+    assert_eq!(
+        serde_json::to_value(&from_xlsx.data).unwrap(),
+        serde_json::to_value(fixtures::grades()).unwrap()
+    );
+}
+
 #[test]
 fn parse_students() {
     let path = String::from("test_students.csv");
     assert!(std::fs::exists(&path).unwrap());
-    let students = process_students_csv_file(path).inspect_err(|err| eprintln!("{err}"));
+    let students = process_students_file(path, &MappingProfile::default(), &Default::default())
+        .inspect_err(|err| eprintln!("{err}"));
     assert!(students.is_ok());
     eprintln!("imported {:#?}", students.unwrap());
 }
+
+#[test]
+fn reject_malformed_user() {
+    let user = ImportIndexUsersResponse {
+        id: Some(Default::default()),
+        om_code_hashed: Some(Some(String::from("hash"))),
+        public_key: Some(Some(String::from("key"))),
+    };
+    assert!(BackboardUser::try_from(user.clone()).is_ok());
+
+    let no_key = ImportIndexUsersResponse {
+        public_key: Some(None),
+        ..user.clone()
+    };
+    let empty_om = ImportIndexUsersResponse {
+        om_code_hashed: Some(Some(String::new())),
+        ..user.clone()
+    };
+    let no_id = ImportIndexUsersResponse { id: None, ..user };
+    assert_eq!(
+        BackboardUser::try_from(no_key).unwrap_err(),
+        UserRecordError::MissingPublicKey
+    );
+    assert_eq!(
+        BackboardUser::try_from(empty_om).unwrap_err(),
+        UserRecordError::MissingOmCodeHashed
+    );
+    assert_eq!(
+        BackboardUser::try_from(no_id).unwrap_err(),
+        UserRecordError::MissingId
+    );
+}
+
+#[test]
+fn map_renamed_columns() {
+    let profile = MappingProfile::default();
+    let columns = profile.students.columns();
+    let headers = StringRecord::from(vec!["Név", "Oktatási azonosítója", "Osztály", "Egyéb"]);
+    assert_eq!(
+        map_headers(&headers, &columns).unwrap(),
+        StringRecord::from(vec!["Name", "OmCode", "Class", "Egyéb"])
+    );
+
+    let renamed = StringRecord::from(vec!["Név", "OM azonosító", "Osztály"]);
+    assert!(matches!(
+        map_headers(&renamed, &columns),
+        Err(InputError::MissingColumns(missing)) if missing == ["Oktatási azonosítója"]
+    ));
+}
+
+#[test]
+fn encrypted_json_round_trip() {
+    use crate::cryptography::kyber_decrypt;
+
+    let vectors: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string("test_envelopes.json").unwrap()).unwrap();
+    let field = |name: &str| vectors[name].as_str().unwrap().to_owned();
+    let collection = fixtures::collection(fixtures::user(&field("publicKey")));
+    // generated by this crate: the wire format can't drift and earlier envelopes keep decrypting,
+    // v1 is decrypted by the server's `KyberKeypairTests` as well, so the two sides can't drift either
+    assert_eq!(
+        serde_json::to_string(&collection).unwrap(),
+        field("plaintext")
+    );
+    for version in ["v1", "v2", "v3"] {
+        assert_eq!(
+            kyber_decrypt(&field(version), field("privateKey")).unwrap(),
+            field("plaintext")
+        );
+    }
+
+    for version in [
+        EnvelopeVersion::V1,
+        EnvelopeVersion::V2,
+        EnvelopeVersion::V3,
+    ] {
+        let encrypted = collection
+            .to_encrypted_json(field("publicKey"), version, &KdfParams::default())
+            .unwrap();
+        assert_eq!(
+            kyber_decrypt(&encrypted, field("privateKey")).unwrap(),
+            field("plaintext")
+        );
+    }
+}
+
+/// input files shared by the tests of every module
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::*;
+
+    /// om code of the only student of `test_grades.csv` and `test_students.csv`
+    pub const OM_CODE: &str = "71624229433";
+
+    /// header of `test_grades.csv`
+    pub const GRADES_HEADER: &str = "Tanuló név;Tanuló osztálya;Születési idő;Tanuló azonosítója;Tárgy kategória;Tantárgy;\
+        Osztály/Csoport név;Pedagógus név;Téma;Értékelés módja;Osztályzat;Jegy;Szöveges értékelés;\
+        Százalékos értékelés;Magatartás;Szorgalom;Bejegyzés dátuma;Rögzítés dátuma;Utolsó mentés dátuma";
+
+    /// the grades of `test_grades.csv`, by hashed om code
+    pub fn grades() -> HashMap<String, Vec<Grade>> {
+        process_grades_file(
+            String::from("test_grades.csv"),
+            &MappingProfile::default(),
+            &Default::default(),
+        )
+        .unwrap()
+        .data
+    }
+
+    /// the students of `test_students.csv`, by hashed om code
+    pub fn students() -> HashMap<String, BackboardStudent> {
+        process_students_file(
+            String::from("test_students.csv"),
+            &MappingProfile::default(),
+            &Default::default(),
+        )
+        .unwrap()
+        .data
+    }
+
+    /// the registered user of [`OM_CODE`], with `public_key`
+    pub fn user(public_key: &str) -> BackboardUser {
+        BackboardUser::try_from(ImportIndexUsersResponse {
+            id: Some(Default::default()),
+            om_code_hashed: Some(Some(crate::cryptography::hash(OM_CODE))),
+            public_key: Some(Some(public_key.to_owned())),
+        })
+        .unwrap()
+    }
+
+    /// the grades of `test_grades.csv`, as they're posted to `user`
+    pub fn collection(user: BackboardUser) -> GradeCollection {
+        let grades = grades().remove(user.om_code_hashed()).unwrap();
+        GradeCollection {
+            grades,
+            school_class: Some(String::from("10.C")),
+            student_name: String::from("Aladar Barnabas"),
+            user,
+        }
+    }
+
+    /// a csv of `header` and `rows`, written to a temp file named after the `test`, returning its path
+    pub fn write_csv(test: &str, header: &str, rows: &[&str]) -> String {
+        let path = std::env::temp_dir().join(format!("backboard-test-{test}.csv"));
+        std::fs::write(
+            &path,
+            [header]
+                .iter()
+                .chain(rows)
+                .map(|line| format!("{line}\n"))
+                .collect::<String>(),
+        )
+        .unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    /// `test_grades.csv` with `rows` instead of its own, see [`write_csv`]
+    pub fn grades_of_rows(test: &str, rows: &[&str]) -> ParsedFile<HashMap<String, Vec<Grade>>> {
+        process_grades_file(
+            write_csv(test, GRADES_HEADER, rows),
+            &MappingProfile::default(),
+            &Default::default(),
+        )
+        .unwrap()
+    }
+}
@@ -0,0 +1,446 @@
+//! # History
+//! structured record of every [import](crate::importer::import_grades) run, kept in a SQLite database\
+//! the GUI keeps it in the app data dir, the CLI next to the log file\
+//! answers questions like "when were 10.C's grades last pushed?", see [`HistoryFilter`]
+
+use crate::report::{ImportReport, UserImportResult, UserOutcome};
+use rusqlite::types::Type;
+use rusqlite::{Connection, Row, params, params_from_iter};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+/// where the CLI keeps the history, next to the log file in the runtime dir
+pub const HISTORY_PATH: &str = ".lovassyapp-backboard-history.sqlite3";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    started_at TEXT NOT NULL,
+    finished_at TEXT NOT NULL,
+    blueboard_url TEXT NOT NULL,
+    import_key_name TEXT,
+    import_key_hash TEXT NOT NULL,
+    input_files TEXT NOT NULL,
+    succeeded INTEGER NOT NULL,
+    skipped INTEGER NOT NULL,
+    unchanged INTEGER NOT NULL,
+    already_imported INTEGER NOT NULL,
+    cancelled INTEGER NOT NULL,
+    failed INTEGER NOT NULL,
+    result TEXT NOT NULL,
+    error TEXT
+);
+CREATE TABLE IF NOT EXISTS run_users (
+    run_id INTEGER NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+    school_class TEXT,
+    outcome TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS run_users_school_class ON run_users(school_class, outcome);
+";
+
+/// current time in UTC, like `2025-09-03T12:00:00Z`, so the timestamps sort as text
+pub fn now() -> String {
+    chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+/// short, non-secret identifier of an import key: the start of its sha256
+pub fn import_key_hash(import_key: &str) -> String {
+    let hash = format!("{:x}", Sha256::digest(import_key));
+    hash[..12].to_owned()
+}
+
+/// couldn't read or write the history
+#[derive(Debug)]
+pub enum HistoryError {
+    Sqlite(rusqlite::Error),
+    Io(io::Error),
+}
+impl fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sqlite(err) => write!(f, "import history: {err}"),
+            Self::Io(err) => write!(f, "import history: {err}"),
+        }
+    }
+}
+impl std::error::Error for HistoryError {}
+impl From<rusqlite::Error> for HistoryError {
+    fn from(e: rusqlite::Error) -> Self {
+        Self::Sqlite(e)
+    }
+}
+impl From<io::Error> for HistoryError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+impl From<serde_json::Error> for HistoryError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Io(e.into())
+    }
+}
+
+/// an input file of a run, identified by the sha256 of its contents
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InputFileHash {
+    pub path: String,
+    /// `None` if the file couldn't be read, the run is recorded anyway
+    pub sha256: Option<String>,
+}
+impl InputFileHash {
+    /// hash the file at `path`
+    pub fn new(path: &str) -> Self {
+        let sha256 = std::fs::read(path)
+            .inspect_err(|e| log::warn!("couldn't hash the input file {path:?}: {e}"))
+            .ok()
+            .map(|contents| format!("{:x}", Sha256::digest(contents)));
+        Self {
+            path: path.to_owned(),
+            sha256,
+        }
+    }
+}
+
+/// number of users per [outcome](UserOutcome) of a run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutcomeCounts {
+    pub succeeded: usize,
+    pub skipped: usize,
+    pub unchanged: usize,
+    pub already_imported: usize,
+    pub cancelled: usize,
+    pub failed: usize,
+}
+impl From<&ImportReport> for OutcomeCounts {
+    fn from(report: &ImportReport) -> Self {
+        let mut counts = Self::default();
+        for result in &report.results {
+            *match result.outcome {
+                UserOutcome::Success => &mut counts.succeeded,
+                UserOutcome::Skipped | UserOutcome::PendingGrades => &mut counts.skipped,
+                UserOutcome::Unchanged => &mut counts.unchanged,
+                UserOutcome::AlreadyImported => &mut counts.already_imported,
+                UserOutcome::Cancelled => &mut counts.cancelled,
+                _ => &mut counts.failed,
+            } += 1;
+        }
+        counts
+    }
+}
+
+/// how a run ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RunResult {
+    Completed,
+    /// the data of some users couldn't be imported
+    PartiallyFailed,
+    Cancelled,
+    /// the import didn't even get to the users, see the error
+    Failed,
+}
+impl From<&ImportReport> for RunResult {
+    fn from(report: &ImportReport) -> Self {
+        let counts = OutcomeCounts::from(report);
+        if counts.cancelled > 0 {
+            Self::Cancelled
+        } else if counts.failed > 0 {
+            Self::PartiallyFailed
+        } else {
+            Self::Completed
+        }
+    }
+}
+impl RunResult {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Completed => "completed",
+            Self::PartiallyFailed => "partiallyFailed",
+            Self::Cancelled => "cancelled",
+            Self::Failed => "failed",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        [
+            Self::Completed,
+            Self::PartiallyFailed,
+            Self::Cancelled,
+            Self::Failed,
+        ]
+        .into_iter()
+        .find(|result| result.as_str() == value)
+    }
+}
+
+/// a run of an import, as recorded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportRun {
+    /// see [`now`]
+    pub started_at: String,
+    pub finished_at: String,
+    pub blueboard_url: String,
+    /// given by the admin, the server doesn't tell
+    pub import_key_name: Option<String>,
+    /// see [`import_key_hash`]
+    pub import_key_hash: String,
+    pub input_files: Vec<InputFileHash>,
+    pub counts: OutcomeCounts,
+    pub result: RunResult,
+    /// why the run [failed](RunResult::Failed)
+    pub error: Option<String>,
+}
+
+/// a recorded run, see [`History::list`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub id: i64,
+    #[serde(flatten)]
+    pub run: ImportRun,
+    /// school classes with at least one student whose data was posted
+    pub classes: Vec<String>,
+}
+
+/// which runs to list, every field narrows it down further
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HistoryFilter {
+    pub blueboard_url: Option<String>,
+    /// the data of at least one student of this class was posted
+    pub school_class: Option<String>,
+    /// started at or after this, like `2025-09-03` or `2025-09-03T12:00:00Z`
+    pub since: Option<String>,
+    /// started at or before this, like `2025-09-03`(the whole day included)
+    pub until: Option<String>,
+    pub result: Option<RunResult>,
+    /// the most recent ones only
+    pub limit: Option<u32>,
+}
+
+/// a json value stored as text in the column `i`
+fn json_column<T: DeserializeOwned>(row: &Row, i: usize) -> rusqlite::Result<T> {
+    serde_json::from_str(&row.get::<_, String>(i)?)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(i, Type::Text, Box::new(e)))
+}
+
+/// a row of the `runs` table, without its classes
+fn entry_from_row(row: &Row) -> rusqlite::Result<HistoryEntry> {
+    Ok(HistoryEntry {
+        id: row.get(0)?,
+        run: ImportRun {
+            started_at: row.get(1)?,
+            finished_at: row.get(2)?,
+            blueboard_url: row.get(3)?,
+            import_key_name: row.get(4)?,
+            import_key_hash: row.get(5)?,
+            input_files: json_column(row, 6)?,
+            counts: OutcomeCounts {
+                succeeded: row.get(7)?,
+                skipped: row.get(8)?,
+                unchanged: row.get(9)?,
+                already_imported: row.get(10)?,
+                cancelled: row.get(11)?,
+                failed: row.get(12)?,
+            },
+            result: RunResult::parse(&row.get::<_, String>(13)?).unwrap_or(RunResult::Failed),
+            error: row.get(14)?,
+        },
+        classes: Vec::new(),
+    })
+}
+
+/// an open history database
+pub struct History {
+    connection: Connection,
+}
+impl History {
+    /// open the history at `path`, creating it if needed
+    /// # Errors
+    /// can't open or create the database
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, HistoryError> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(&format!("PRAGMA foreign_keys = ON;{SCHEMA}"))?;
+        Ok(Self { connection })
+    }
+
+    /// note the `run` with the result of each of its `users`, returns its id
+    /// # Errors
+    /// can't write the database
+    pub fn record(
+        &mut self,
+        run: &ImportRun,
+        users: &[UserImportResult],
+    ) -> Result<i64, HistoryError> {
+        let transaction = self.connection.transaction()?;
+        let counts = &run.counts;
+        transaction.execute(
+            "INSERT INTO runs (started_at, finished_at, blueboard_url, import_key_name, import_key_hash, input_files,
+                succeeded, skipped, unchanged, already_imported, cancelled, failed, result, error)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            params![
+                run.started_at,
+                run.finished_at,
+                run.blueboard_url,
+                run.import_key_name,
+                run.import_key_hash,
+                serde_json::to_string(&run.input_files)?,
+                counts.succeeded,
+                counts.skipped,
+                counts.unchanged,
+                counts.already_imported,
+                counts.cancelled,
+                counts.failed,
+                run.result.as_str(),
+                run.error,
+            ],
+        )?;
+        let id = transaction.last_insert_rowid();
+        {
+            let mut insert = transaction.prepare(
+                "INSERT INTO run_users (run_id, user_id, school_class, outcome) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for user in users {
+                insert.execute(params![
+                    id,
+                    user.user_id,
+                    user.school_class,
+                    user.outcome.kind(),
+                ])?;
+            }
+        }
+        transaction.commit()?;
+        log::info!("import run recorded in the history as {id}");
+        Ok(id)
+    }
+
+    /// the runs matching the `filter`, the most recent first
+    /// # Errors
+    /// can't read the database
+    pub fn list(&self, filter: &HistoryFilter) -> Result<Vec<HistoryEntry>, HistoryError> {
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+        let mut condition = |sql: &str, value: &str| {
+            values.push(value.to_owned());
+            conditions.push(sql.replace('?', &format!("?{}", values.len())));
+        };
+        if let Some(url) = &filter.blueboard_url {
+            condition("blueboard_url = ?", url);
+        }
+        if let Some(class) = &filter.school_class {
+            condition(
+                "id IN (SELECT run_id FROM run_users WHERE school_class = ? AND outcome = 'success')",
+                class,
+            );
+        }
+        if let Some(since) = &filter.since {
+            condition("started_at >= ?", since);
+        }
+        if let Some(until) = &filter.until {
+            condition("substr(started_at, 1, length(?)) <= ?", until);
+        }
+        if let Some(result) = filter.result {
+            condition("result = ?", result.as_str());
+        }
+
+        let mut sql = String::from(
+            "SELECT id, started_at, finished_at, blueboard_url, import_key_name, import_key_hash, input_files,
+                succeeded, skipped, unchanged, already_imported, cancelled, failed, result, error FROM runs",
+        );
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        sql.push_str(" ORDER BY started_at DESC, id DESC");
+        if let Some(limit) = filter.limit {
+            sql.push_str(&format!(" LIMIT {limit}"));
+        }
+
+        let mut select = self.connection.prepare(&sql)?;
+        let mut entries = select
+            .query_map(params_from_iter(&values), entry_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut classes = self.connection.prepare(
+            "SELECT DISTINCT school_class FROM run_users
+             WHERE run_id = ?1 AND outcome = 'success' AND school_class IS NOT NULL ORDER BY school_class",
+        )?;
+        for entry in &mut entries {
+            entry.classes = classes
+                .query_map([entry.id], |row| row.get(0))?
+                .collect::<Result<_, _>>()?;
+        }
+        Ok(entries)
+    }
+}
+
+#[test]
+fn filter_history() {
+    let path = std::env::temp_dir().join("backboard-test-history.sqlite3");
+    let _ = std::fs::remove_file(&path);
+    let mut history = History::open(&path).unwrap();
+    let user = |class: &str, outcome| UserImportResult {
+        user_id: String::from("1"),
+        student_name: Some(String::from("Buga Matyi")),
+        school_class: Some(class.to_owned()),
+        outcome,
+    };
+    let run = |started_at: &str, users: &[UserImportResult]| ImportRun {
+        started_at: started_at.to_owned(),
+        finished_at: started_at.to_owned(),
+        blueboard_url: String::from("url"),
+        import_key_name: Some(String::from("titkárság")),
+        import_key_hash: import_key_hash("key"),
+        input_files: Vec::new(),
+        counts: OutcomeCounts::from(&ImportReport {
+            results: users.to_vec(),
+        }),
+        result: RunResult::Completed,
+        error: None,
+    };
+
+    let first = [user("10.C", UserOutcome::Success)];
+    history
+        .record(&run("2025-09-03T08:00:00Z", &first), &first)
+        .unwrap();
+    let second = [
+        user("10.C", UserOutcome::Skipped),
+        user("9.A", UserOutcome::Success),
+    ];
+    history
+        .record(&run("2025-09-10T08:00:00Z", &second), &second)
+        .unwrap();
+
+    let all = history.list(&HistoryFilter::default()).unwrap();
+    assert_eq!(all.len(), 2);
+    assert_eq!(all[0].run.started_at, "2025-09-10T08:00:00Z");
+    assert_eq!(all[0].classes, ["9.A"]);
+    assert_eq!(all[0].run.counts.skipped, 1);
+
+    let of_class = HistoryFilter {
+        school_class: Some(String::from("10.C")),
+        ..Default::default()
+    };
+    let of_class = history.list(&of_class).unwrap();
+    assert_eq!(of_class.len(), 1);
+    assert_eq!(of_class[0].run, run("2025-09-03T08:00:00Z", &first));
+
+    let until = HistoryFilter {
+        until: Some(String::from("2025-09-03")),
+        ..Default::default()
+    };
+    assert_eq!(history.list(&until).unwrap().len(), 1);
+    let since = HistoryFilter {
+        since: Some(String::from("2025-09-04")),
+        result: Some(RunResult::Completed),
+        ..Default::default()
+    };
+    assert_eq!(history.list(&since).unwrap()[0].classes, ["9.A"]);
+}
@@ -0,0 +1,1353 @@
+//! # Importer
+//! the grade import pipeline itself, shared by the *GUI* (Tauri commands) and the headless *CLI*\
+//! progress is reported through [`ImportEvent`]s, the callers decide where these end up
+
+use crate::coverage::CoverageReport;
+use crate::cryptography::{EnvelopeVersion, KdfParams, OmHashing};
+use crate::filter::{FilterSummary, GradeFilter};
+use crate::fingerprints::{self, Fingerprints};
+use crate::grade::{self, Grade};
+use crate::grades_processor::{
+    BackboardStudent, BackboardUser, GradeCollection, InputError, InputFormat, UserRecordError,
+    process_grades_file, process_students_file,
+};
+use crate::history::{
+    self, History, HistoryError, ImportRun, InputFileHash, OutcomeCounts, RunResult,
+};
+use crate::journal::{self, Journal, JournalHeader};
+use crate::mapping::MappingProfile;
+use crate::redact::Redacted;
+use crate::report::{ImportReport, UserImportResult, UserOutcome};
+use crate::uploader;
+use crate::validator::RowIssue;
+use api::apis::Error;
+use api::apis::configuration::{ApiKey, Configuration};
+use api::apis::import_api::api_import_reset_key_password_put;
+use api::models::{ImportIndexUsersResponse, ImportUpdateResetKeyPasswordRequestBody};
+use api::paging::{DEFAULT_PAGE_SIZE, api_import_users_pages};
+use futures::{StreamExt, stream};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::pin::pin;
+use tokio_util::sync::CancellationToken;
+
+/// everything an import needs, coming from the GUI settings or the CLI flags
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportOptions {
+    /// grades exports, possibly overlapping ones, e.g. one per class or semester, see [`grade::merge_grades`]
+    pub grades_file_paths: Vec<String>,
+    pub students_file_path: Option<String>,
+    pub blueboard_url: String,
+    #[serde(default)]
+    pub reset_key_password: String,
+    pub import_key: String,
+    #[serde(default)]
+    pub update_reset_key_password: bool,
+    /// skip the users already posted by an interrupted run with the same inputs, see [`find_resumable`]
+    #[serde(default)]
+    pub resume: bool,
+    /// import the valid rows even if some rows of the input files are invalid, see [`validate_input`]
+    #[serde(default)]
+    pub skip_invalid_rows: bool,
+    /// json or toml [profile](MappingProfile) of the input files' columns, the built-in one if not given
+    #[serde(default)]
+    pub mapping_profile_path: Option<String>,
+    /// skip the users whose data hasn't changed since it was last posted to them, see [`fingerprints`]
+    #[serde(default)]
+    pub changes_only: bool,
+    /// import only some of the grades, e.g. of a single class\
+    /// it replaces the users' pending imports, see [`filter`](crate::filter)
+    #[serde(default)]
+    pub filter: GradeFilter,
+    /// name of the `import_key` given by the admin, recorded in the [history]
+    #[serde(default)]
+    pub import_key_name: Option<String>,
+    /// the server's pepper of the [keyed om code hashes](OmHashing), users are matched by the legacy hashes only without it
+    #[serde(default)]
+    pub om_code_pepper: Option<String>,
+    /// record the run in the [history] at this path, set by the callers, not the GUI
+    #[serde(skip)]
+    pub history_path: Option<PathBuf>,
+    /// keep the [journal] at this path instead of [`journal::JOURNAL_PATH`], set by the callers, not the GUI
+    #[serde(skip)]
+    pub journal_path: Option<PathBuf>,
+    /// keep the [`fingerprints`] at this path instead of [`fingerprints::FINGERPRINTS_PATH`], set by the callers, not the GUI
+    #[serde(skip)]
+    pub fingerprints_path: Option<PathBuf>,
+}
+
+/// progress report of a running import
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportEvent {
+    /// number of users fetched from the server so far, while still fetching
+    Fetching(usize),
+    /// an input file got parsed, this is how it was read, reported for each grades file
+    InputFormat(DetectedFormat),
+    /// number of users(already registered students) fetched from the server
+    Users(usize),
+    /// percentage of users already processed
+    Progress(usize),
+    /// the import is over, either completed or cancelled
+    Summary(ImportSummary),
+}
+
+/// one of the input files of an import
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InputFile {
+    Grades,
+    Students,
+}
+
+/// how an input `file` was read, see [`ImportEvent::InputFormat`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectedFormat {
+    pub file: InputFile,
+    pub format: InputFormat,
+}
+
+/// rows of the input files left out due to problems, see [`validate_input`]
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InputValidation {
+    pub grades: Vec<RowIssue>,
+    pub students: Vec<RowIssue>,
+}
+impl InputValidation {
+    pub fn len(&self) -> usize {
+        self.grades.len() + self.students.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// how far an import got
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    /// users done with, whatever the outcome, except the ones left out due to cancellation
+    pub processed: usize,
+    pub total: usize,
+    pub cancelled: bool,
+}
+
+/// everything that can go wrong during an import
+#[derive(Debug)]
+pub enum ImportError {
+    /// the server responded with this http error status code
+    Response(u16),
+    /// the request couldn't be sent, or the response couldn't be understood
+    Request(String),
+    /// coming from [`process_students_file`] and/or [`process_grades_file`]
+    Input(InputError),
+    /// this many rows of the input files are invalid, and they weren't allowed to be skipped
+    InvalidRows(usize),
+    /// couldn't read the input files or write the [journal]
+    Io(std::io::Error),
+}
+impl ImportError {
+    /// the server refused the `import_key`
+    pub fn is_unauthorized(&self) -> bool {
+        matches!(self, Self::Response(401 | 403))
+    }
+}
+impl fmt::Display for ImportError {
+    /// the GUI matches on the bare status code, so keep it that way
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Response(status) => write!(f, "{status}"),
+            Self::Request(err) => write!(f, "{err}"),
+            Self::Input(err) => write!(f, "{err}"),
+            Self::InvalidRows(num_rows) => {
+                write!(f, "{num_rows} invalid row(s) in the input files")
+            }
+            Self::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+impl std::error::Error for ImportError {}
+
+/// extract http error status code from the [api error](api::apis::Error) if available, otherwise convert it into a string
+impl<E: fmt::Debug> From<Error<E>> for ImportError {
+    fn from(e: Error<E>) -> Self {
+        log::error!("error: {e:#?}");
+        match e {
+            Error::ResponseError(response_error) => Self::Response(response_error.status.as_u16()),
+            other_err => Self::Request(other_err.to_string()),
+        }
+    }
+}
+impl From<std::io::Error> for ImportError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+impl From<InputError> for ImportError {
+    fn from(e: InputError) -> Self {
+        Self::Input(e)
+    }
+}
+
+/// [`Configuration`] to reach the server at `blueboard_url`, authorized with the `import_key`
+pub fn api_config(blueboard_url: String, import_key: String) -> Configuration {
+    Configuration {
+        base_path: blueboard_url,
+        api_key: Some(ApiKey {
+            prefix: None,
+            key: import_key,
+        }),
+        ..Configuration::new()
+    }
+}
+
+/// upload the `reset_key_password` to the server at `blueboard_url`, using the `import_key`
+/// # Errors
+/// invalid `import_key`, something with the PUT request
+pub async fn upload_reset_key_password(
+    blueboard_url: String,
+    reset_key_password: String,
+    import_key: String,
+) -> Result<(), ImportError> {
+    let config = api_config(blueboard_url, import_key);
+
+    log::info!("uploading reset key password");
+    api_import_reset_key_password_put(
+        &config,
+        Some(ImportUpdateResetKeyPasswordRequestBody::new(
+            reset_key_password,
+        )),
+    )
+    .await?;
+    Ok(())
+}
+
+/// upload all the new [grades][crate::grade::Grade] to each registered user's account on the server\
+/// **NOTE**: imported data won't be visible right away, check out the server code to see what happens ;)\
+/// if `students_file_path` is provided: upload|update the information of the students\
+/// if `update_rest_key_password`: upload the `reset_key_password`\
+/// if `changes_only`: skip the users whose data is the same as the last time, see [`fingerprints`]\
+/// `report` gets called with every [`ImportEvent`]\
+/// a user failing doesn't stop the others, the returned [`ImportReport`] tells what happened to each of them,
+/// malformed user records coming from the server included\
+/// once `cancel` is cancelled, no new uploads are started, but the ones in-flight are finished\
+/// if `history_path` is set, the run gets recorded in the [history], failed ones included
+/// # Errors
+/// - coming from [`upload_reset_key_password`]
+/// - invalid `import_key`
+/// - something with the PUT or GET requests
+/// - coming from [`process_students_file`] and/or [`process_grades_file`]
+/// - invalid rows in the input files, unless `skip_invalid_rows`
+///
+/// a [journal] or [`fingerprints`] that can't be written only cost resuming or skipping the unchanged users,
+/// it's logged but doesn't stop the import
+pub async fn import_grades(
+    options: ImportOptions,
+    cancel: &CancellationToken,
+    report: impl Fn(ImportEvent),
+) -> Result<ImportReport, ImportError> {
+    let started_at = history::now();
+    let result = run_import(&options, cancel, report).await;
+    if let Some(path) = &options.history_path
+        && let Err(e) = record_history(path, &options, started_at, &result)
+    {
+        log::warn!("couldn't record the import in the history: {e}");
+    }
+    result
+}
+
+/// see [`import_grades`]
+async fn run_import(
+    options: &ImportOptions,
+    cancel: &CancellationToken,
+    report: impl Fn(ImportEvent),
+) -> Result<ImportReport, ImportError> {
+    log::info!("importing grades");
+    if !options.filter.is_empty() {
+        log::warn!(
+            "filtered import, it replaces the pending imports of the users who haven't logged in since the last one"
+        );
+    }
+    if options.update_reset_key_password {
+        upload_reset_key_password(
+            options.blueboard_url.clone(),
+            options.reset_key_password.clone(),
+            options.import_key.clone(),
+        )
+        .await?;
+        log::info!("successfully uploaded reset key password");
+    }
+
+    let config = api_config(options.blueboard_url.clone(), options.import_key.clone());
+    let ImportData {
+        users,
+        invalid_users,
+        imported_grade_map,
+        imported_student_info_map,
+        ..
+    } = load_import_data(&config, options, &report).await?;
+    let num_users = users.len() + invalid_users.len();
+    let mut journal = match Journal::start(
+        journal_path(options),
+        &journal_header(options)?,
+        options.resume,
+    ) {
+        Ok(journal) => Some(journal),
+        Err(e) => {
+            log::warn!("couldn't open the journal, the import won't be resumable: {e}");
+            None
+        }
+    };
+    let mut fingerprints = Fingerprints::load(fingerprints_path(options), &options.blueboard_url);
+
+    // users without grades are done right away, the rest gets uploaded concurrently
+    let mut results = Vec::with_capacity(num_users);
+    let mut uploads = Vec::new();
+    results.extend(invalid_user_results(invalid_users, users.len()));
+    for (i, user) in users.into_iter().enumerate() {
+        log::debug!("processing user: {:?}", Redacted(&user));
+        let user_id = user.id().to_owned();
+        if journal.as_ref().is_some_and(|j| j.is_done(&user_id)) {
+            log::info!("user {user_id} already done by an earlier run");
+            results.push((
+                i,
+                UserImportResult {
+                    user_id,
+                    student_name: None,
+                    school_class: None,
+                    outcome: UserOutcome::AlreadyImported,
+                },
+            ));
+            continue;
+        }
+        match prepare_user(&user, &imported_grade_map, &imported_student_info_map) {
+            Some(PreparedUser { collection, .. }) => {
+                let fingerprint = fingerprints::fingerprint(&collection);
+                if options.changes_only
+                    && fingerprints.is_unchanged(user.om_code_hashed(), &fingerprint)
+                {
+                    log::info!("data of user {user_id} unchanged since the last import");
+                    results.push((
+                        i,
+                        UserImportResult {
+                            user_id,
+                            student_name: Some(collection.student_name),
+                            school_class: collection.school_class,
+                            outcome: UserOutcome::Unchanged,
+                        },
+                    ));
+                } else {
+                    uploads.push((i, user, collection, fingerprint));
+                }
+            }
+            None => results.push((
+                i,
+                UserImportResult {
+                    user_id,
+                    student_name: None,
+                    school_class: None,
+                    outcome: UserOutcome::Skipped,
+                },
+            )),
+        }
+    }
+    report(ImportEvent::Progress(
+        results.len() * 100 / num_users.max(1),
+    ));
+
+    let config = &config;
+    let mut uploads = stream::iter(uploads)
+        .map(|(i, user, collection, fingerprint)| async move {
+            // uploads not started yet when cancelled are left out
+            let outcome = if cancel.is_cancelled() {
+                UserOutcome::Cancelled
+            } else {
+                upload_collection(config, &user, &collection, cancel).await
+            };
+            let result = UserImportResult {
+                user_id: user.id().to_owned(),
+                student_name: Some(collection.student_name),
+                school_class: collection.school_class,
+                outcome,
+            };
+            let hashed_om = user.om_code_hashed().to_owned();
+            (i, result, hashed_om, fingerprint)
+        })
+        .buffer_unordered(uploader::MAX_CONCURRENT_UPLOADS);
+    while let Some((i, result, hashed_om, fingerprint)) = uploads.next().await {
+        if result.outcome == UserOutcome::Success {
+            if let Some(journal) = &mut journal
+                && let Err(e) = journal.record(&result.user_id)
+            {
+                log::warn!("couldn't write the journal, user won't be skipped on resume: {e}");
+            }
+            fingerprints.record(hashed_om, fingerprint);
+        }
+        results.push((i, result));
+        report(ImportEvent::Progress(results.len() * 100 / num_users));
+    }
+
+    if let Err(e) = fingerprints.save() {
+        log::warn!(
+            "couldn't save the fingerprints, the next changes only import will post everyone: {e}"
+        );
+    }
+
+    let (import_report, num_cancelled) = finish_report(results, num_users, &report);
+    let num_failed = import_report.num_failed();
+    if num_failed > 0 {
+        log::error!("failed to import the data of {num_failed} user(s), the import can be resumed");
+    } else if num_cancelled > 0 {
+        log::info!("import cancelled before {num_cancelled} user(s), it can be resumed");
+    } else if let Some(journal) = journal
+        && let Err(e) = journal.finish()
+    {
+        log::warn!("couldn't remove the journal of the completed import: {e}");
+    }
+
+    Ok(import_report)
+}
+
+/// results of the user records that can't be imported, indexed after the `num_valid` valid ones
+fn invalid_user_results(
+    invalid_users: Vec<(String, UserRecordError)>,
+    num_valid: usize,
+) -> impl Iterator<Item = (usize, UserImportResult)> {
+    invalid_users
+        .into_iter()
+        .enumerate()
+        .map(move |(i, (user_id, e))| {
+            let result = UserImportResult {
+                user_id,
+                student_name: None,
+                school_class: None,
+                outcome: UserOutcome::InvalidRecord {
+                    message: e.to_string(),
+                },
+            };
+            (num_valid + i, result)
+        })
+}
+
+/// put the indexed `results` back in the order of the server and report the [summary](ImportEvent::Summary),
+/// returns the number of users left out due to cancellation as well
+fn finish_report(
+    mut results: Vec<(usize, UserImportResult)>,
+    num_users: usize,
+    report: &impl Fn(ImportEvent),
+) -> (ImportReport, usize) {
+    results.sort_unstable_by_key(|(i, _)| *i);
+    let import_report = ImportReport {
+        results: results.into_iter().map(|(_, result)| result).collect(),
+    };
+    let num_cancelled = import_report
+        .results
+        .iter()
+        .filter(|r| r.outcome == UserOutcome::Cancelled)
+        .count();
+    if num_cancelled == 0 {
+        report(ImportEvent::Progress(100));
+    } else {
+        log::warn!("cancelled, {num_cancelled} user(s) left out");
+    }
+    report(ImportEvent::Summary(ImportSummary {
+        processed: num_users - num_cancelled,
+        total: num_users,
+        cancelled: num_cancelled > 0,
+    }));
+    (import_report, num_cancelled)
+}
+
+/// note the run of an import with `options`, started at `started_at` and ended with `result`, in the [history] at `path`
+fn record_history(
+    path: &Path,
+    options: &ImportOptions,
+    started_at: String,
+    result: &Result<ImportReport, ImportError>,
+) -> Result<(), HistoryError> {
+    let input_files = options
+        .grades_file_paths
+        .iter()
+        .chain(&options.students_file_path)
+        .map(|path| InputFileHash::new(path))
+        .collect();
+    let (counts, result, error, users) = match result {
+        Ok(report) => (
+            OutcomeCounts::from(report),
+            RunResult::from(report),
+            None,
+            report.results.as_slice(),
+        ),
+        Err(e) => (
+            OutcomeCounts::default(),
+            RunResult::Failed,
+            Some(e.to_string()),
+            [].as_slice(),
+        ),
+    };
+    let run = ImportRun {
+        started_at,
+        finished_at: history::now(),
+        blueboard_url: options.blueboard_url.clone(),
+        import_key_name: options.import_key_name.clone(),
+        import_key_hash: history::import_key_hash(&options.import_key),
+        input_files,
+        counts,
+        result,
+        error,
+    };
+    History::open(path)?.record(&run, users)?;
+    Ok(())
+}
+
+/// format of the encrypted data\
+/// the server's `KyberKeypair.Decrypt` only parses [`EnvelopeVersion::V1`] so far,
+/// the newer ones are to be chosen once it can tell which ones it decrypts
+pub const ENVELOPE_VERSION: EnvelopeVersion = EnvelopeVersion::V1;
+
+/// encrypt and post the `collection` of `user`\
+/// deriving the key takes a while, so the encryption runs on a blocking thread
+async fn upload_collection(
+    config: &Configuration,
+    user: &BackboardUser,
+    collection: &GradeCollection,
+    cancel: &CancellationToken,
+) -> UserOutcome {
+    let user_id = user.id();
+    let pub_key = user.public_key().to_owned(); // public key used for encryption
+    log::debug!("user's public key: {:?}", Redacted(&pub_key));
+
+    let encryption = tokio::task::spawn_blocking({
+        let collection = collection.clone();
+        move || collection.to_encrypted_json(pub_key, ENVELOPE_VERSION, &KdfParams::default())
+    });
+    let json_encrypted = match encryption.await {
+        Ok(Ok(json_encrypted)) => json_encrypted,
+        Ok(Err(e)) => {
+            log::error!("couldn't encrypt data of user {user_id}: {e}");
+            return UserOutcome::EncryptionError {
+                cause: Some(e.kind().to_owned()),
+                message: e.to_string(),
+            };
+        }
+        Err(e) => {
+            log::error!("encrypting data of user {user_id} panicked: {e}");
+            return UserOutcome::EncryptionError {
+                cause: None,
+                message: e.to_string(),
+            };
+        }
+    };
+
+    log::info!("posting data of user {user_id}");
+    match uploader::post_grades(config, user_id, json_encrypted, cancel).await {
+        Ok(()) => {
+            log::info!("successfully posted data of user {user_id}");
+            UserOutcome::Success
+        }
+        Err(e) => {
+            log::error!("couldn't post data of user {user_id}: {e}");
+            e.into()
+        }
+    }
+}
+
+fn journal_header(options: &ImportOptions) -> std::io::Result<JournalHeader> {
+    let header = JournalHeader::new(
+        &options.grades_file_paths,
+        options.students_file_path.as_deref(),
+        &options.blueboard_url,
+    )?;
+    Ok(JournalHeader {
+        filter: (!options.filter.is_empty()).then(|| options.filter.clone()),
+        ..header
+    })
+}
+
+/// where the [journal] of the import of `options` is kept
+fn journal_path(options: &ImportOptions) -> &Path {
+    options
+        .journal_path
+        .as_deref()
+        .unwrap_or(Path::new(journal::JOURNAL_PATH))
+}
+
+/// where the [`fingerprints`] of the import of `options` are kept
+fn fingerprints_path(options: &ImportOptions) -> &Path {
+    options
+        .fingerprints_path
+        .as_deref()
+        .unwrap_or(Path::new(fingerprints::FINGERPRINTS_PATH))
+}
+
+/// number of users already posted by an interrupted import with the same inputs and server,
+/// `None` if there's nothing to resume, or the [journal] can't be read
+/// # Errors
+/// can't read the input files
+pub fn find_resumable(options: &ImportOptions) -> Result<Option<usize>, ImportError> {
+    let completed =
+        journal::find(journal_path(options), &journal_header(options)?).unwrap_or_else(|e| {
+            log::warn!("couldn't read the journal, nothing to resume: {e}");
+            None
+        });
+    Ok(completed.map(|completed| completed.len()))
+}
+
+/// check the rows of the input files of `options`, without reaching the server\
+/// an import refuses to start if there are any invalid ones, unless `skip_invalid_rows`
+/// # Errors
+/// coming from [`process_students_file`] and/or [`process_grades_file`]
+pub fn validate_input(options: &ImportOptions) -> Result<InputValidation, ImportError> {
+    log::info!("validating input files");
+    let profile = MappingProfile::load_or_default(options.mapping_profile_path.as_deref())
+        .map_err(InputError::from)?;
+    let hashing = OmHashing::new(options.om_code_pepper.clone());
+    let grades = load_grades(&options.grades_file_paths, &profile, &hashing, &|_| {})?.issues;
+    let students = match &options.students_file_path {
+        Some(path) => process_students_file(path.clone(), &profile, &hashing)?.issues,
+        None => Vec::new(),
+    };
+    Ok(InputValidation { grades, students })
+}
+
+/// everything a [roster sync](sync_roster) or [coverage check](check_coverage) needs, coming from the GUI settings or the CLI flags
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RosterOptions {
+    pub students_file_path: String,
+    pub blueboard_url: String,
+    pub import_key: String,
+    /// sync the valid rows even if some rows of the students file are invalid
+    #[serde(default)]
+    pub skip_invalid_rows: bool,
+    /// json or toml [profile](MappingProfile) of the students file's columns, the built-in one if not given
+    #[serde(default)]
+    pub mapping_profile_path: Option<String>,
+    /// the server's pepper of the [keyed om code hashes](OmHashing), users are matched by the legacy hashes only without it
+    #[serde(default)]
+    pub om_code_pepper: Option<String>,
+    /// sync the users that have been posted grades as well, see [`sync_roster`]
+    #[serde(default)]
+    pub overwrite_pending_grades: bool,
+    /// where the [`fingerprints`] are kept, [`fingerprints::FINGERPRINTS_PATH`] if not given, set by the callers, not the GUI
+    #[serde(skip)]
+    pub fingerprints_path: Option<PathBuf>,
+}
+
+/// push the names and classes of the students file to every registered user found in it, without any grades\
+/// meant for the start of the school year, when classes change before any grades exist\
+/// the server only keeps the latest import of a user until they log in, so this one replaces any grades posted to
+/// them since their last login, which can't be told from here: the users that have been posted grades
+/// (according to the [`fingerprints`]) are [left out](UserOutcome::PendingGrades), unless `overwrite_pending_grades`\
+/// `report` gets called with every [`ImportEvent`], users not in the students file are [skipped](UserOutcome::Skipped)\
+/// once `cancel` is cancelled, no new uploads are started, but the ones in-flight are finished
+/// # Errors
+/// - invalid `import_key`
+/// - something with the GET requests
+/// - coming from [`process_students_file`]
+/// - invalid rows in the students file, unless `skip_invalid_rows`
+pub async fn sync_roster(
+    options: RosterOptions,
+    cancel: &CancellationToken,
+    report: impl Fn(ImportEvent),
+) -> Result<ImportReport, ImportError> {
+    log::info!("syncing roster");
+    let config = api_config(options.blueboard_url.clone(), options.import_key.clone());
+    let (users, invalid_users) = validate_users(fetch_users(&config, &report).await?);
+    let students = load_roster_students(
+        &options,
+        users.iter().map(BackboardUser::om_code_hashed),
+        &report,
+    )?;
+    let fingerprints_path = options
+        .fingerprints_path
+        .as_deref()
+        .unwrap_or(Path::new(fingerprints::FINGERPRINTS_PATH));
+    let mut fingerprints = Fingerprints::load(fingerprints_path, &options.blueboard_url);
+
+    let num_users = users.len() + invalid_users.len();
+    let mut results = Vec::with_capacity(num_users);
+    results.extend(invalid_user_results(invalid_users, users.len()));
+    let mut uploads = Vec::new();
+    for (i, user) in users.into_iter().enumerate() {
+        match students.get(user.om_code_hashed()) {
+            Some(student)
+                if !options.overwrite_pending_grades
+                    && fingerprints.has_posted(user.om_code_hashed()) =>
+            {
+                results.push((
+                    i,
+                    UserImportResult {
+                        user_id: user.id().to_owned(),
+                        student_name: Some(student.name.clone()),
+                        school_class: Some(student.class.clone()),
+                        outcome: UserOutcome::PendingGrades,
+                    },
+                ));
+            }
+            Some(student) => {
+                let collection = GradeCollection {
+                    grades: Vec::new(),
+                    school_class: Some(student.class.clone()),
+                    student_name: student.name.clone(),
+                    user: user.clone(),
+                };
+                uploads.push((i, user, collection));
+            }
+            None => results.push((
+                i,
+                UserImportResult {
+                    user_id: user.id().to_owned(),
+                    student_name: None,
+                    school_class: None,
+                    outcome: UserOutcome::Skipped,
+                },
+            )),
+        }
+    }
+    report(ImportEvent::Progress(
+        results.len() * 100 / num_users.max(1),
+    ));
+
+    let config = &config;
+    let mut uploads = stream::iter(uploads)
+        .map(|(i, user, collection)| async move {
+            let outcome = if cancel.is_cancelled() {
+                UserOutcome::Cancelled
+            } else {
+                upload_collection(config, &user, &collection, cancel).await
+            };
+            let result = UserImportResult {
+                user_id: user.id().to_owned(),
+                student_name: Some(collection.student_name),
+                school_class: collection.school_class,
+                outcome,
+            };
+            (i, result, user)
+        })
+        .buffer_unordered(uploader::MAX_CONCURRENT_UPLOADS);
+    while let Some((i, result, user)) = uploads.next().await {
+        if result.outcome == UserOutcome::Success {
+            // the grades posted before are gone, the next changes only import has to post them again
+            fingerprints.forget(user.om_code_hashed());
+        }
+        results.push((i, result));
+        report(ImportEvent::Progress(results.len() * 100 / num_users));
+    }
+    if let Err(e) = fingerprints.save() {
+        log::warn!(
+            "couldn't save the fingerprints, the next changes only import may skip overwritten users: {e}"
+        );
+    }
+
+    let (roster_report, _) = finish_report(results, num_users, &report);
+    let num_pending = roster_report
+        .results
+        .iter()
+        .filter(|r| r.outcome == UserOutcome::PendingGrades)
+        .count();
+    if num_pending > 0 {
+        log::warn!(
+            "left out {num_pending} user(s) who have been posted grades, their pending grades would be lost"
+        );
+    }
+    let num_failed = roster_report.num_failed();
+    if num_failed > 0 {
+        log::error!("failed to sync the info of {num_failed} user(s)");
+    }
+    Ok(roster_report)
+}
+
+/// the students file of a roster sync or coverage check, [matched](match_keyed_hashes) to the `registered` users
+/// # Errors
+/// - coming from [`process_students_file`]
+/// - invalid rows in the students file, unless `skip_invalid_rows`
+fn load_roster_students<'a>(
+    options: &RosterOptions,
+    registered: impl IntoIterator<Item = &'a str>,
+    report: &impl Fn(ImportEvent),
+) -> Result<StudentMap, ImportError> {
+    let profile = MappingProfile::load_or_default(options.mapping_profile_path.as_deref())
+        .map_err(InputError::from)?;
+    let hashing = OmHashing::new(options.om_code_pepper.clone());
+    let mut students =
+        process_students_file(options.students_file_path.clone(), &profile, &hashing)?;
+    report(ImportEvent::InputFormat(DetectedFormat {
+        file: InputFile::Students,
+        format: students.format,
+    }));
+    let num_invalid_rows = students.issues.len();
+    if num_invalid_rows > 0 {
+        if !options.skip_invalid_rows {
+            return Err(ImportError::InvalidRows(num_invalid_rows));
+        }
+        log::warn!("skipping {num_invalid_rows} invalid row(s) of the students file");
+    }
+    match_keyed_hashes(&mut students.data, &students.keyed_hashes, registered);
+    Ok(students.data)
+}
+
+/// re-key the entries of the legacy-hash keyed `map` to the [keyed hashes](OmHashing) of the `registered` users
+/// found in `keyed_hashes`, so while the users migrate, users of either hash get matched
+fn match_keyed_hashes<'a, V>(
+    map: &mut HashMap<String, V>,
+    keyed_hashes: &HashMap<String, String>,
+    registered: impl IntoIterator<Item = &'a str>,
+) {
+    let mut num_keyed = 0;
+    for hashed_om in registered {
+        if let Some(legacy) = keyed_hashes.get(hashed_om)
+            && let Some(value) = map.remove(legacy)
+        {
+            map.insert(hashed_om.to_owned(), value);
+            num_keyed += 1;
+        }
+    }
+    if num_keyed > 0 {
+        log::info!("{num_keyed} user(s) matched by their keyed om code hash");
+    }
+}
+
+/// which students of the students file haven't registered yet, see [`CoverageReport`]\
+/// only the hashed om codes of the server's users are compared to the locally hashed ones of the file,
+/// nothing gets uploaded
+/// # Errors
+/// - invalid `import_key`
+/// - something with the GET requests
+/// - coming from [`process_students_file`]
+/// - invalid rows in the students file, unless `skip_invalid_rows`
+pub async fn check_coverage(
+    options: RosterOptions,
+    report: impl Fn(ImportEvent),
+) -> Result<CoverageReport, ImportError> {
+    log::info!("checking registration coverage");
+    let config = api_config(options.blueboard_url.clone(), options.import_key.clone());
+    let users = fetch_users(&config, &report).await?;
+    // malformed user records count as registered as long as their om code is there
+    let registered = users
+        .iter()
+        .filter_map(|user| user.om_code_hashed.as_ref()?.as_deref())
+        .collect::<Vec<_>>();
+    let students = load_roster_students(&options, registered.iter().copied(), &report)?;
+    Ok(CoverageReport::new(&students, registered))
+}
+
+/// fetch every user(already registered student) from the server, page by page\
+/// reports the number of users fetched so far as [`ImportEvent::Fetching`], then the total as [`ImportEvent::Users`]
+/// # Errors
+/// invalid `import_key`, something with the GET requests
+pub async fn fetch_users(
+    config: &Configuration,
+    report: &impl Fn(ImportEvent),
+) -> Result<Vec<ImportIndexUsersResponse>, ImportError> {
+    log::info!("fetching users");
+    let mut users = Vec::new();
+    let mut seen_ids = HashSet::new();
+    // sorted by id, so the pages stay put
+    let mut pages = pin!(api_import_users_pages(
+        config,
+        None,
+        Some("Id"),
+        DEFAULT_PAGE_SIZE
+    ));
+    while let Some(page) = pages.next().await {
+        let page = page?;
+        log::debug!("fetched a page of {} users", page.len());
+        users.extend(unseen_users(page, &mut seen_ids));
+        report(ImportEvent::Fetching(users.len()));
+    }
+    log::info!("users fetched from server already there ({})", users.len());
+    log::trace!("{:?}", Redacted(&users));
+
+    report(ImportEvent::Users(users.len()));
+    Ok(users)
+}
+
+/// the users of a fetched `page` not on an earlier one, someone registering mid-fetch may shift a user
+/// to the next page as well\
+/// records without an id are all kept, they are reported as [invalid](UserRecordError) later
+fn unseen_users(
+    page: Vec<ImportIndexUsersResponse>,
+    seen_ids: &mut HashSet<String>,
+) -> impl Iterator<Item = ImportIndexUsersResponse> {
+    page.into_iter()
+        .filter(|user| user.id.is_none_or(|id| seen_ids.insert(id.to_string())))
+}
+
+/// hashed-om-code mapped [grades](Grade)
+type GradeMap = HashMap<String, Vec<Grade>>;
+/// hashed-om-code mapped [students](BackboardStudent)
+type StudentMap = HashMap<String, BackboardStudent>;
+
+/// the users fetched from the server and the parsed input files, see [`load_import_data`]
+struct ImportData {
+    users: Vec<BackboardUser>,
+    /// ids(empty if missing) of the user records that can't be imported, with the reason
+    invalid_users: Vec<(String, UserRecordError)>,
+    imported_grade_map: GradeMap,
+    /// grades found in more than one of the grades files(or twice in one), left out
+    num_duplicate_grades: usize,
+    imported_student_info_map: StudentMap,
+    /// what the [filter](ImportOptions::filter) selected, `None` if there's none
+    filter_summary: Option<FilterSummary>,
+}
+
+/// the grades files of an import, merged, see [`load_grades`]
+struct MergedGrades {
+    grades: GradeMap,
+    /// of every file, each one tagged with the name of its file
+    issues: Vec<RowIssue>,
+    num_duplicates: usize,
+    /// of every file, see [`ParsedFile::keyed_hashes`]
+    keyed_hashes: HashMap<String, String>,
+}
+
+/// parse the grades files at `paths` and [merge](grade::merge_grades) them,
+/// the format of each is reported as [`ImportEvent::InputFormat`]
+/// # Errors
+/// coming from [`process_grades_file`]
+fn load_grades(
+    paths: &[String],
+    profile: &MappingProfile,
+    hashing: &OmHashing,
+    report: &impl Fn(ImportEvent),
+) -> Result<MergedGrades, InputError> {
+    let mut exports = Vec::with_capacity(paths.len());
+    let mut issues = Vec::new();
+    let mut keyed_hashes = HashMap::new();
+    for path in paths {
+        let grades = process_grades_file(path.clone(), profile, hashing)?;
+        report(ImportEvent::InputFormat(DetectedFormat {
+            file: InputFile::Grades,
+            format: grades.format,
+        }));
+        let file = Path::new(path)
+            .file_name()
+            .map_or_else(|| path.clone(), |name| name.to_string_lossy().into_owned());
+        issues.extend(grades.issues.into_iter().map(|issue| RowIssue {
+            file: Some(file.clone()),
+            ..issue
+        }));
+        exports.push(grades.data);
+        keyed_hashes.extend(grades.keyed_hashes);
+    }
+    let (grades, num_duplicates) = grade::merge_grades(exports);
+    Ok(MergedGrades {
+        grades,
+        issues,
+        num_duplicates,
+        keyed_hashes,
+    })
+}
+
+/// split the user records fetched from the server into valid and [invalid](UserRecordError) ones, logging the latter
+fn validate_users(
+    users: Vec<ImportIndexUsersResponse>,
+) -> (Vec<BackboardUser>, Vec<(String, UserRecordError)>) {
+    let mut valid = Vec::with_capacity(users.len());
+    let mut invalid = Vec::new();
+    for user in users {
+        let user_id = user.id.map(|id| id.to_string()).unwrap_or_default();
+        match BackboardUser::try_from(user) {
+            Ok(user) => valid.push(user),
+            Err(e) => {
+                log::error!("skipping user {user_id:?}: {e}");
+                invalid.push((user_id, e));
+            }
+        }
+    }
+    (valid, invalid)
+}
+
+/// fetch the users(already registered students) from the server and parse the input files of `options`,
+/// then apply its [filter](ImportOptions::filter)
+async fn load_import_data(
+    config: &Configuration,
+    options: &ImportOptions,
+    report: &impl Fn(ImportEvent),
+) -> Result<ImportData, ImportError> {
+    // fetches data of users(already registered students) from the server, will add imported data to these later
+    let (users, invalid_users) = validate_users(fetch_users(config, report).await?);
+
+    let profile = MappingProfile::load_or_default(options.mapping_profile_path.as_deref())
+        .map_err(InputError::from)?;
+    let hashing = OmHashing::new(options.om_code_pepper.clone());
+    let mut grades = load_grades(&options.grades_file_paths, &profile, &hashing, report)?;
+    let mut num_invalid_rows = grades.issues.len();
+
+    let mut imported_student_info_map = if let Some(path) = &options.students_file_path {
+        let students = process_students_file(path.clone(), &profile, &hashing)?;
+        report(ImportEvent::InputFormat(DetectedFormat {
+            file: InputFile::Students,
+            format: students.format,
+        }));
+        num_invalid_rows += students.issues.len();
+        grades.keyed_hashes.extend(students.keyed_hashes);
+        students.data
+    } else {
+        HashMap::new() // leave it empty if file path not provided
+    };
+    if num_invalid_rows > 0 {
+        if !options.skip_invalid_rows {
+            return Err(ImportError::InvalidRows(num_invalid_rows));
+        }
+        log::warn!("skipping {num_invalid_rows} invalid row(s) of the input files");
+    }
+
+    let mut imported_grade_map = grades.grades;
+    let registered = || users.iter().map(BackboardUser::om_code_hashed);
+    match_keyed_hashes(&mut imported_grade_map, &grades.keyed_hashes, registered());
+    match_keyed_hashes(
+        &mut imported_student_info_map,
+        &grades.keyed_hashes,
+        registered(),
+    );
+    let filter_summary = (!options.filter.is_empty()).then(|| {
+        options
+            .filter
+            .apply(&mut imported_grade_map, &imported_student_info_map)
+    });
+
+    Ok(ImportData {
+        users,
+        invalid_users,
+        imported_grade_map,
+        num_duplicate_grades: grades.num_duplicates,
+        imported_student_info_map,
+        filter_summary,
+    })
+}
+
+/// a registered user joined with the freshly imported data belonging to them
+struct PreparedUser {
+    collection: GradeCollection,
+    /// name and class weren't found in the students' data, so they were taken from the grades
+    info_from_grades: bool,
+}
+
+/// pack useful information about `user` to be sent, `None` if there are no imported grades for them
+fn prepare_user(
+    user: &BackboardUser,
+    imported_grade_map: &GradeMap,
+    imported_student_info_map: &StudentMap,
+) -> Option<PreparedUser> {
+    let hashed_om = user.om_code_hashed(); // used as key to its data
+    let Some(user_grades) = imported_grade_map.get(hashed_om) else {
+        log::warn!("no imported grades found");
+        return None;
+    };
+    log::trace!(
+        "user's freshly imported grades: {:?}",
+        Redacted(user_grades)
+    );
+
+    // extract student info from data provided, fall back to grades sometimes containing it
+    let student_info = imported_student_info_map.get(hashed_om);
+    let (school_class, student_name) = if let Some(student_info) = student_info {
+        (Some(&student_info.class), &student_info.name)
+    } else {
+        log::warn!("user not found in students' data, falling back to grades");
+        let cls = user_grades.iter().find_map(|g| g.school_class.as_ref());
+        (cls, &user_grades[0].student_name)
+    };
+    log::debug!("user's school class: {:?}", Redacted(school_class));
+    log::debug!("user's name: {:?}", Redacted(student_name));
+
+    let collection = GradeCollection {
+        grades: user_grades.clone(),
+        school_class: school_class.cloned(),
+        student_name: student_name.clone(),
+        user: user.clone(),
+    };
+    log::trace!("user's grade collection: {:?}", Redacted(&collection));
+
+    Some(PreparedUser {
+        collection,
+        info_from_grades: student_info.is_none(),
+    })
+}
+
+/// a registered user who would receive grades
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewUser {
+    pub id: String,
+    pub student_name: String,
+    pub school_class: Option<String>,
+    pub grade_count: usize,
+    /// name and class weren't found in the students' data, so they were taken from the grades
+    pub info_from_grades: bool,
+    /// the very same data was posted the last time, skipped by a changes only import
+    pub unchanged: bool,
+}
+
+/// a student found in the input files, who has no account on the server
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewStudent {
+    pub student_name: String,
+    pub school_class: Option<String>,
+    pub grade_count: usize,
+}
+
+/// a user record from the server that would be skipped, see [`UserRecordError`]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvalidUser {
+    /// empty if missing
+    pub id: String,
+    pub message: String,
+}
+
+/// what an import with the same options would send, see [`preview_import`]
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportPreview {
+    pub matched_users: Vec<PreviewUser>,
+    /// ids of registered users without any imported grades
+    pub users_without_grades: Vec<String>,
+    pub students_without_account: Vec<PreviewStudent>,
+    pub invalid_users: Vec<InvalidUser>,
+    /// grades found in more than one of the grades files, sent only once
+    pub duplicate_grades: usize,
+    /// what the [filter](ImportOptions::filter) selected, `None` if there's none
+    pub filter: Option<FilterSummary>,
+}
+
+/// dry-run of [`import_grades`]: joins the users fetched from the server with the input files
+/// and builds every [`GradeCollection`], but neither encrypts nor uploads anything\
+/// the `reset_key_password` is left untouched as well, invalid rows of the input files are skipped,
+/// see [`validate_input`] for those
+/// # Errors
+/// - invalid `import_key`
+/// - something with the GET request
+/// - can't read the [`fingerprints`]
+pub async fn preview_import(
+    mut options: ImportOptions,
+    report: impl Fn(ImportEvent),
+) -> Result<ImportPreview, ImportError> {
+    log::info!("previewing grade import");
+    options.skip_invalid_rows = true;
+    let config = api_config(options.blueboard_url.clone(), options.import_key.clone());
+    let ImportData {
+        users,
+        invalid_users,
+        imported_grade_map,
+        num_duplicate_grades,
+        imported_student_info_map,
+        filter_summary,
+    } = load_import_data(&config, &options, &report).await?;
+
+    let mut preview = ImportPreview {
+        duplicate_grades: num_duplicate_grades,
+        filter: filter_summary,
+        invalid_users: invalid_users
+            .into_iter()
+            .map(|(id, e)| InvalidUser {
+                id,
+                message: e.to_string(),
+            })
+            .collect(),
+        ..Default::default()
+    };
+    let fingerprints = Fingerprints::load(fingerprints_path(&options), &options.blueboard_url);
+    let mut registered = HashSet::new();
+    for user in &users {
+        registered.insert(user.om_code_hashed());
+        let id = user.id().to_owned();
+        match prepare_user(user, &imported_grade_map, &imported_student_info_map) {
+            Some(PreparedUser {
+                collection,
+                info_from_grades,
+            }) => preview.matched_users.push(PreviewUser {
+                unchanged: fingerprints.is_unchanged(
+                    user.om_code_hashed(),
+                    &fingerprints::fingerprint(&collection),
+                ),
+                id,
+                student_name: collection.student_name,
+                school_class: collection.school_class,
+                grade_count: collection.grades.len(),
+                info_from_grades,
+            }),
+            None => preview.users_without_grades.push(id),
+        }
+    }
+
+    let unregistered_oms = imported_grade_map
+        .keys()
+        .chain(imported_student_info_map.keys())
+        .filter(|hashed_om| !registered.contains(hashed_om.as_str()))
+        .collect::<HashSet<_>>();
+    for hashed_om in unregistered_oms {
+        let grades = imported_grade_map.get(hashed_om);
+        let (student_name, school_class) = match imported_student_info_map.get(hashed_om) {
+            Some(student) => (student.name.clone(), Some(student.class.clone())),
+            None => {
+                let grades = grades.expect("either grades or student info is present");
+                let cls = grades.iter().find_map(|g| g.school_class.clone());
+                (grades[0].student_name.clone(), cls)
+            }
+        };
+        preview.students_without_account.push(PreviewStudent {
+            student_name,
+            school_class,
+            grade_count: grades.map_or(0, Vec::len),
+        });
+    }
+    preview.students_without_account.sort_by(|a, b| {
+        (&a.school_class, &a.student_name).cmp(&(&b.school_class, &b.student_name))
+    });
+    log::info!(
+        "preview: {} matched users, {} users without grades, {} students without account, {} invalid users",
+        preview.matched_users.len(),
+        preview.users_without_grades.len(),
+        preview.students_without_account.len(),
+        preview.invalid_users.len()
+    );
+
+    Ok(preview)
+}
+
+#[test]
+fn prepare_test_user() {
+    use crate::grades_processor::fixtures;
+
+    let grades = fixtures::grades();
+    let students = fixtures::students();
+    let user = fixtures::user("key");
+
+    let prepared = prepare_user(&user, &grades, &students).unwrap();
+    assert!(!prepared.info_from_grades);
+    assert_eq!(prepared.collection.student_name, "Buga Matyi");
+    assert_eq!(prepared.collection.school_class.as_deref(), Some("9.A"));
+
+    let prepared = prepare_user(&user, &grades, &HashMap::new()).unwrap();
+    assert!(prepared.info_from_grades);
+    assert_eq!(prepared.collection.student_name, "Aladar Barnabas");
+    assert_eq!(prepared.collection.school_class.as_deref(), Some("10.C"));
+
+    assert!(prepare_user(&user, &HashMap::new(), &students).is_none());
+}
+
+#[test]
+fn match_keyed_om_codes() {
+    let hashing = OmHashing::new(Some(String::from("pepper")));
+    let students = process_students_file(
+        String::from("test_students.csv"),
+        &MappingProfile::default(),
+        &hashing,
+    )
+    .unwrap();
+    let legacy = crate::cryptography::hash("71624229433");
+    let keyed = crate::cryptography::keyed_hash("71624229433", "pepper");
+    assert_ne!(legacy, keyed);
+    assert_eq!(students.keyed_hashes.get(&keyed), Some(&legacy));
+    assert_eq!(
+        hashing.hash("71624229433"),
+        (legacy.clone(), Some(keyed.clone()))
+    );
+    assert!(OmHashing::new(Some(String::new())).pepper.is_none());
+
+    let mut legacy_users = students.data.clone();
+    match_keyed_hashes(&mut legacy_users, &students.keyed_hashes, [legacy.as_str()]);
+    assert!(legacy_users.contains_key(&legacy));
+
+    let mut migrated_users = students.data;
+    match_keyed_hashes(
+        &mut migrated_users,
+        &students.keyed_hashes,
+        [keyed.as_str()],
+    );
+    assert!(migrated_users.contains_key(&keyed));
+    assert!(!migrated_users.contains_key(&legacy));
+}
+
+#[test]
+fn skip_users_seen_on_earlier_pages() {
+    let user = |id| ImportIndexUsersResponse {
+        id,
+        om_code_hashed: None,
+        public_key: None,
+    };
+    let id = Some(Default::default());
+    let mut seen_ids = HashSet::new();
+    assert_eq!(
+        unseen_users(vec![user(id), user(None)], &mut seen_ids).count(),
+        2
+    );
+    // the first one got shifted, the ones without an id can't be told apart
+    let unseen = unseen_users(vec![user(id), user(None), user(None)], &mut seen_ids);
+    assert!(unseen.map(|user| user.id).eq([None, None]));
+}
+
+#[test]
+fn record_import_runs() {
+    use crate::history::{HistoryFilter, InputFileHash, RunResult};
+
+    let path = std::env::temp_dir().join("backboard-test-import-history.sqlite3");
+    let _ = std::fs::remove_file(&path);
+    let options: ImportOptions = serde_json::from_value(serde_json::json!({
+        "gradesFilePaths": ["test_grades.csv"],
+        "studentsFilePath": "test_students.csv",
+        "blueboardUrl": "url",
+        "importKey": "key",
+        "importKeyName": "titkárság",
+    }))
+    .unwrap();
+    let report = ImportReport {
+        results: vec![UserImportResult {
+            user_id: String::from("1"),
+            student_name: Some(String::from("Buga Matyi")),
+            school_class: Some(String::from("9.A")),
+            outcome: UserOutcome::Success,
+        }],
+    };
+    record_history(&path, &options, history::now(), &Ok(report)).unwrap();
+    // failed runs are recorded even if an input file is gone
+    let missing = ImportOptions {
+        grades_file_paths: vec![String::from("missing_grades.csv")],
+        ..options.clone()
+    };
+    record_history(
+        &path,
+        &missing,
+        history::now(),
+        &Err(ImportError::Response(500)),
+    )
+    .unwrap();
+
+    let entries = History::open(&path)
+        .unwrap()
+        .list(&HistoryFilter::default())
+        .unwrap();
+    let results = entries.iter().map(|e| e.run.result).collect::<Vec<_>>();
+    assert!(results.contains(&RunResult::Completed) && results.contains(&RunResult::Failed));
+    let failed = entries
+        .iter()
+        .find(|e| e.run.result == RunResult::Failed)
+        .unwrap();
+    assert_eq!(failed.run.error.as_deref(), Some("500"));
+    assert_eq!(failed.run.input_files[0].sha256, None);
+    assert!(failed.classes.is_empty());
+    let completed = entries
+        .iter()
+        .find(|e| e.run.result == RunResult::Completed)
+        .unwrap();
+    assert_eq!(completed.classes, ["9.A"]);
+    assert_eq!(completed.run.counts.succeeded, 1);
+    // the files are known by their hashes, the import key only by a prefix of its own
+    assert_eq!(
+        completed.run.input_files,
+        [
+            InputFileHash::new("test_grades.csv"),
+            InputFileHash::new("test_students.csv")
+        ]
+    );
+    assert_ne!(completed.run.import_key_hash, "key");
+}
@@ -0,0 +1,160 @@
+//! # Journal
+//! local checkpoint of a running import, so an interrupted one can be resumed instead of starting over\
+//! the first line identifies the import (hash of the input files, the server url and the filter),
+//! each further line is the id of a user whose data has already been posted
+
+use crate::filter::GradeFilter;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// where the journal is kept by default, in the working dir, the GUI keeps it in the app data dir instead
+pub const JOURNAL_PATH: &str = ".lovassyapp-backboard.journal";
+
+/// identifies an import, a journal can only be resumed with the very same one
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalHeader {
+    /// sha256 of the grades and students files' contents
+    pub inputs_hash: String,
+    pub blueboard_url: String,
+    /// the [filter](crate::filter::GradeFilter) of the import, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<GradeFilter>,
+}
+impl JournalHeader {
+    /// hash the contents of the input files
+    /// # Errors
+    /// can't read the files
+    pub fn new(
+        grades_file_paths: &[String],
+        students_file_path: Option<&str>,
+        blueboard_url: &str,
+    ) -> io::Result<Self> {
+        let mut hasher = Sha256::new();
+        for (i, path) in grades_file_paths.iter().enumerate() {
+            if i > 0 {
+                hasher.update(b"\0");
+            }
+            hasher.update(fs::read(path)?);
+        }
+        if let Some(path) = students_file_path {
+            hasher.update(b"\0");
+            hasher.update(fs::read(path)?);
+        }
+        Ok(Self {
+            inputs_hash: format!("{:x}", hasher.finalize()),
+            blueboard_url: blueboard_url.to_owned(),
+            filter: None,
+        })
+    }
+}
+
+/// ids of users already posted by an earlier run with the same `header`, `None` if there's no such journal at `path`
+/// # Errors
+/// can't read the journal
+pub fn find(path: impl AsRef<Path>, header: &JournalHeader) -> io::Result<Option<HashSet<String>>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let mut lines = BufReader::new(file).lines();
+    let Some(first) = lines.next().transpose()? else {
+        return Ok(None);
+    };
+    match serde_json::from_str::<JournalHeader>(&first) {
+        Ok(found) if found == *header => {}
+        _ => return Ok(None), // another import, or not a journal at all
+    }
+    lines
+        .map(|line| line.map(|id| id.trim().to_owned()))
+        .filter(|id| !id.as_ref().is_ok_and(String::is_empty))
+        .collect::<io::Result<_>>()
+        .map(Some)
+}
+
+/// an open journal of the running import
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+    completed: HashSet<String>,
+}
+impl Journal {
+    /// open the journal at `path` for the import identified by `header`\
+    /// if `resume` and it belongs to the same import, the users posted so far are kept, otherwise it starts over
+    /// # Errors
+    /// can't read or write the journal
+    pub fn start(
+        path: impl Into<PathBuf>,
+        header: &JournalHeader,
+        resume: bool,
+    ) -> io::Result<Self> {
+        let path = path.into();
+        let completed = if resume { find(&path, header)? } else { None };
+        let file = if let Some(completed) = &completed {
+            log::info!("resuming import, {} user(s) already done", completed.len());
+            OpenOptions::new().append(true).open(&path)?
+        } else {
+            let mut file = File::create(&path)?;
+            writeln!(file, "{}", serde_json::to_string(header)?)?;
+            file
+        };
+        Ok(Self {
+            path,
+            file,
+            completed: completed.unwrap_or_default(),
+        })
+    }
+
+    /// the data of the user with `user_id` has already been posted
+    pub fn is_done(&self, user_id: &str) -> bool {
+        self.completed.contains(user_id)
+    }
+
+    /// note that the data of the user with `user_id` has been posted
+    /// # Errors
+    /// can't write the journal
+    pub fn record(&mut self, user_id: &str) -> io::Result<()> {
+        writeln!(self.file, "{user_id}")?;
+        self.file.flush()?;
+        self.completed.insert(user_id.to_owned());
+        Ok(())
+    }
+
+    /// the import is complete, nothing left to resume: remove the journal
+    /// # Errors
+    /// can't remove the journal
+    pub fn finish(self) -> io::Result<()> {
+        drop(self.file);
+        fs::remove_file(self.path)
+    }
+}
+
+#[test]
+fn resume_journal() {
+    let path = std::env::temp_dir().join("backboard-test.journal");
+    let grades = [String::from("test_grades.csv")];
+    let header = JournalHeader::new(&grades, Some("test_students.csv"), "url").unwrap();
+    let other = JournalHeader::new(&grades, None, "url").unwrap();
+    assert_ne!(header, other);
+
+    let mut journal = Journal::start(&path, &header, false).unwrap();
+    journal.record("a").unwrap();
+    journal.record("b").unwrap();
+    drop(journal);
+
+    assert_eq!(find(&path, &other).unwrap(), None);
+    let journal = Journal::start(&path, &header, true).unwrap();
+    assert!(journal.is_done("a") && journal.is_done("b") && !journal.is_done("c"));
+    drop(journal);
+
+    let journal = Journal::start(&path, &header, false).unwrap();
+    assert!(!journal.is_done("a"));
+    journal.finish().unwrap();
+    assert!(!path.exists());
+}
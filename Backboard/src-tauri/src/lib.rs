@@ -0,0 +1,63 @@
+#![doc = include_str!("../../README.md")]
+
+pub mod coverage;
+pub mod cryptography;
+pub mod filter;
+pub mod fingerprints;
+pub mod grade;
+pub mod grades_processor;
+pub mod history;
+pub mod importer;
+pub mod journal;
+pub mod mapping;
+pub mod redact;
+pub mod report;
+pub mod sniffer;
+pub mod uploader;
+pub mod validator;
+
+use std::path::Path;
+
+/// where the CLI keeps the logs in the runtime dir, in a new file every day, see [`init_logger`]
+pub const LOG_DIR: &str = ".lovassyapp-backboard-logs";
+/// the log files are rotated beyond this size as well
+pub const LOG_MAX_FILE_SIZE_MB: u64 = 10;
+/// the log files older than this are removed by the daily logger
+pub const LOG_RETENTION_DAYS: u64 = 14;
+/// the single, never rotated log file of earlier versions outside [`LOG_DIR`], removed on startup once it's older than the retention limit
+const LEGACY_LOG_PATH: &str = ".lovassyapp-backboard.log";
+
+/// logs to `stderr` and a daily file in `log_dir` as well, both respecting `RUST_LOG`\
+/// only to `stderr` if `log_dir` is `None` or can't be created, like in a read-only working dir\
+/// personal data is [redacted](redact) unless `unsafe_debug`, the logs older than [`LOG_RETENTION_DAYS`] are removed
+/// # Panics
+/// if a logger is already set
+pub fn init_logger(unsafe_debug: bool, log_dir: Option<&Path>) {
+    let created = log_dir.map(|dir| std::fs::create_dir_all(dir).map(|()| dir));
+    match created {
+        Some(Ok(log_dir)) => ftail::Ftail::new()
+            .max_file_size(LOG_MAX_FILE_SIZE_MB)
+            .console_env_level()
+            .daily_file_env_level(log_dir)
+            .retention_days(LOG_RETENTION_DAYS),
+        _ => ftail::Ftail::new().console_env_level(),
+    }
+    .init()
+    .unwrap();
+    if let Some(Err(e)) = created {
+        log::warn!("logging to the console only, couldn't create the log dir: {e}");
+    }
+    redact::set_unsafe_debug(unsafe_debug);
+
+    let retention = std::time::Duration::from_secs(LOG_RETENTION_DAYS * 24 * 60 * 60);
+    let legacy_log = Path::new(LEGACY_LOG_PATH);
+    if std::fs::metadata(legacy_log)
+        .and_then(|md| md.modified())
+        .is_ok_and(|modified| modified.elapsed().unwrap_or_default() > retention)
+    {
+        match std::fs::remove_file(legacy_log) {
+            Ok(()) => log::info!("removed the log file of an earlier version"),
+            Err(e) => log::warn!("couldn't remove the log file of an earlier version: {e}"),
+        }
+    }
+}
@@ -1,172 +1,217 @@
-#![doc = include_str!("../../README.md")]
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-mod cryptography;
-mod grades_processor;
-
 use api::apis::Error;
-use api::apis::configuration::{ApiKey, Configuration};
-use api::apis::import_api::{
-    api_import_grades_user_id_post, api_import_reset_key_password_put, api_import_users_get,
-};
+use api::apis::configuration::Configuration;
 use api::apis::status_api::api_status_service_status_get;
-use api::models::{
-    ImportImportGradesRequestBody, ImportUpdateResetKeyPasswordRequestBody,
-    StatusViewServiceStatusResponse,
+use api::models::StatusViewServiceStatusResponse;
+use backboard::coverage::CoverageReport;
+use backboard::history::{History, HistoryEntry, HistoryFilter};
+use backboard::importer::{
+    self, ImportError, ImportEvent, ImportOptions, ImportPreview, InputValidation, RosterOptions,
 };
-use grades_processor::{GradeCollection, process_grades_csv_file, process_students_csv_file};
-use std::collections::HashMap;
-use tauri::{Emitter, Window};
+use backboard::redact;
+use backboard::report::ImportReport;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager, State, Window};
 use tauri_plugin_autostart::MacosLauncher;
+use tokio_util::sync::CancellationToken;
 
 /// extract http error status code from the [api error](api::apis::Error) if available, otherwise convert it into a string
 fn handle_api_err<E: std::fmt::Debug>(e: Error<E>) -> String {
-    log::error!("error: {e:#?}");
-    match e {
-        Error::ResponseError(response_error) => response_error.status.as_str().to_string(),
-        other_err => other_err.to_string(),
-    }
+    ImportError::from(e).to_string()
 }
 
 /// upload the `reset_key_password` to the server at `blueboard_url`, using the `import_key`
 /// # Errors
-/// invalid `import_key`, something with the PUT request
+/// coming from [`importer::upload_reset_key_password`]
 #[tauri::command]
 async fn upload_reset_key_password(
     blueboard_url: String,
     reset_key_password: String,
     import_key: String,
 ) -> Result<(), String> {
-    let mut config = Configuration::new();
-    config.base_path = blueboard_url;
-    config.api_key = Some(ApiKey {
-        prefix: None,
-        key: import_key,
-    });
-
-    log::info!("uploading reset key password");
-    api_import_reset_key_password_put(
-        &config,
-        Some(ImportUpdateResetKeyPasswordRequestBody::new(
-            reset_key_password,
-        )),
-    )
-    .await
-    .map_err(handle_api_err)
-}
-
-/// upload all the new [grades][grades_processor::BackboardGrade] to each registered user's account on the server\
-/// **NOTE**: imported data won't be visible right away, check out the server code to see what happens ;)\
-/// if `students_file_path` is provided: upload|update the information of the students\
-/// if `update_rest_key_password`: upload the `reset_key_password`
+    importer::upload_reset_key_password(blueboard_url, reset_key_password, import_key)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// cancellation token of the running import, replaced by each new one
+#[derive(Default)]
+struct ImportCancellation(Mutex<CancellationToken>);
+
+/// forward [`ImportEvent`]s to the GUI as `{prefix}-fetching`, `{prefix}-input-format`, `{prefix}-users`,
+/// `{prefix}-progress` and `{prefix}-summary` window events
+fn report_to_window<'a>(window: &'a Window, prefix: &'static str) -> impl Fn(ImportEvent) + 'a {
+    move |event| {
+        let emitted = match event {
+            ImportEvent::Fetching(num_users) => {
+                window.emit(&format!("{prefix}-fetching"), num_users)
+            }
+            ImportEvent::InputFormat(detected) => {
+                window.emit(&format!("{prefix}-input-format"), detected)
+            }
+            ImportEvent::Users(num_users) => window.emit(&format!("{prefix}-users"), num_users),
+            ImportEvent::Progress(percent) => window.emit(&format!("{prefix}-progress"), percent),
+            ImportEvent::Summary(summary) => window.emit(&format!("{prefix}-summary"), summary),
+        };
+        if let Err(e) = emitted {
+            log::warn!("couldn't report {event:?} to the GUI: {e}");
+        }
+    }
+}
+
+/// `file_name` in the app data dir, creating the dir if needed\
+/// the working dir of the GUI is arbitrary, it may not even be writable
+fn app_data_path(window: &Window, file_name: &str) -> Result<PathBuf, String> {
+    let dir = window.path().app_data_dir().map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir.join(file_name))
+}
+
+/// the [import history](backboard::history) in the app data dir
+fn history_path(window: &Window) -> Result<PathBuf, String> {
+    app_data_path(window, "import-history.sqlite3")
+}
+
+/// the [journal](backboard::journal) in the app data dir, the working dir if that isn't available
+fn journal_path(window: &Window) -> Option<PathBuf> {
+    app_data_path(window, "import.journal")
+        .inspect_err(|e| log::warn!("keeping the journal in the working dir: {e}"))
+        .ok()
+}
+
+/// the [fingerprints](backboard::fingerprints) in the app data dir, the working dir if that isn't available
+fn fingerprints_path(window: &Window) -> Option<PathBuf> {
+    app_data_path(window, "fingerprints.json")
+        .inspect_err(|e| log::warn!("keeping the fingerprints in the working dir: {e}"))
+        .ok()
+}
+
+/// run the [import pipeline](importer::import_grades), reporting to the GUI through window events\
+/// can be stopped by [`cancel_import`], every run is recorded in the [import history](list_import_history)
 /// # Errors
-/// - coming from [`upload_reset_key_password`]
-/// - invalid `import_key`
-/// - something with the PUT or GET requests
-/// - coming from [`process_students_csv_file`] and/or [`process_grades_csv_file`]
+/// coming from [`importer::import_grades`]
 #[tauri::command]
 async fn import_grades(
     window: Window,
-    grades_file_path: String,
-    students_file_path: Option<String>,
-    blueboard_url: String,
-    reset_key_password: String,
-    import_key: String,
-    update_reset_key_password: bool,
-) -> Result<(), String> {
-    log::info!("importing grades");
-    if update_reset_key_password {
-        upload_reset_key_password(
-            blueboard_url.clone(),
-            reset_key_password,
-            import_key.clone(),
-        )
-        .await?;
-        log::info!("successfully uploaded reset key password");
+    cancellation: State<'_, ImportCancellation>,
+    mut options: ImportOptions,
+) -> Result<ImportReport, String> {
+    match history_path(&window) {
+        Ok(path) => options.history_path = Some(path),
+        Err(e) => log::warn!("import won't be recorded in the history: {e}"),
     }
+    options.journal_path = journal_path(&window);
+    options.fingerprints_path = fingerprints_path(&window);
+    let cancel = CancellationToken::new();
+    *cancellation.0.lock().unwrap() = cancel.clone();
+    importer::import_grades(options, &cancel, report_to_window(&window, "import"))
+        .await
+        .map_err(|e| e.to_string())
+}
 
-    let config = Configuration {
-        base_path: blueboard_url,
-        api_key: Some(ApiKey {
-            prefix: None,
-            key: import_key,
-        }),
-        ..Configuration::new()
-    };
-
-    // fetches data of users(already registered students) from the server, will add imported data to these later
-    let users = api_import_users_get(&config, None, None, None, None)
+/// push the names and classes of the students file to the matched users, without grades,
+/// reporting to the GUI through `roster-*` window events\
+/// can be stopped by [`cancel_import`]
+/// # Errors
+/// coming from [`importer::sync_roster`]
+#[tauri::command]
+async fn sync_roster(
+    window: Window,
+    cancellation: State<'_, ImportCancellation>,
+    mut options: RosterOptions,
+) -> Result<ImportReport, String> {
+    options.fingerprints_path = fingerprints_path(&window);
+    let cancel = CancellationToken::new();
+    *cancellation.0.lock().unwrap() = cancel.clone();
+    importer::sync_roster(options, &cancel, report_to_window(&window, "roster"))
         .await
-        .map_err(handle_api_err)?;
-    let num_users = users.len();
-    log::info!("users fetched from server already there ({num_users})");
-    log::trace!("{users:?}");
-
-    window.emit("import-users", num_users).unwrap(); // GUI report
-
-    let imported_grade_map =
-        process_grades_csv_file(grades_file_path).map_err(|err| err.to_string())?;
-
-    let imported_student_info_map = if let Some(path) = students_file_path {
-        process_students_csv_file(path).map_err(|err| err.to_string())?
-    } else {
-        HashMap::new() // leave it empty if file path not provided
-    };
-
-    let mut count = 0; // number of users already processed
-    for user in users {
-        log::debug!("processing {count}. user: {user:?}");
-        let hashed_om = &user.om_code_hashed.clone().unwrap().unwrap(); // used as key to its data
-        let Some(user_grades) = imported_grade_map.get(hashed_om) else {
-            log::warn!("no imported grades found");
-            continue;
-        };
-        log::trace!("user's freshly imported grades: {user_grades:?}");
-
-        let pub_key = user.public_key.clone().unwrap().unwrap(); // public key used for encryption
-        log::debug!("user's public key: {pub_key:?}");
-
-        // extract student info from data provided, fall back to grades sometimes containing it
-        let (school_class, student_name) =
-            if let Some(student_info) = &imported_student_info_map.get(hashed_om) {
-                (Some(&student_info.class), &student_info.name)
-            } else {
-                log::warn!("user not found in students' data, falling back to grades");
-                let cls = user_grades.iter().find_map(|g| g.school_class.as_ref());
-                (cls, &user_grades[0].student_name)
-            };
-        log::debug!("user's school class: {school_class:?}");
-        log::debug!("user's name: {student_name}");
-
-        // pack useful information about user to be sent
-        let grade_collection = GradeCollection {
-            grades: user_grades.clone(),
-            school_class: school_class.cloned(),
-            student_name: student_name.clone(),
-            user: user.clone().into(),
-        };
-        log::trace!("user's grade collection: {grade_collection:?}");
-
-        log::info!("posting user's data");
-        api_import_grades_user_id_post(
-            &config,
-            &user.id.unwrap().to_string(),
-            Some(ImportImportGradesRequestBody {
-                json_encrypted: grade_collection.to_encrypted_json(pub_key)?,
-            }),
-        )
+        .map_err(|e| e.to_string())
+}
+
+/// students of the students file without a registered user
+/// # Errors
+/// coming from [`importer::check_coverage`]
+#[tauri::command]
+async fn check_coverage(window: Window, options: RosterOptions) -> Result<CoverageReport, String> {
+    importer::check_coverage(options, report_to_window(&window, "coverage"))
         .await
-        .map_err(handle_api_err)?;
-        log::info!("successfully posted user's data");
+        .map_err(|e| e.to_string())
+}
 
-        count += 1;
-        window
-            .emit("import-progress", (count / num_users) * 100)
-            .unwrap(); // GUI progress report
-    }
+/// save the coverage `report` to `path`, as json if it ends with `.json`, as csv otherwise
+/// # Errors
+/// can't write to `path`
+#[tauri::command]
+fn export_coverage_report(report: CoverageReport, path: String) -> Result<(), String> {
+    report.export(path)
+}
+
+/// stop the running import or roster sync once the in-flight uploads are finished
+#[tauri::command]
+fn cancel_import(cancellation: State<'_, ImportCancellation>) {
+    log::info!("cancelling import");
+    cancellation.0.lock().unwrap().cancel();
+}
+
+/// number of users already imported by an interrupted import with the same `options`, `None` if there's nothing to resume
+/// # Errors
+/// coming from [`importer::find_resumable`]
+#[tauri::command]
+fn resumable_import(window: Window, mut options: ImportOptions) -> Result<Option<usize>, String> {
+    options.journal_path = journal_path(&window);
+    importer::find_resumable(&options).map_err(|e| e.to_string())
+}
+
+/// problems of the rows of the input files of `options`, the server isn't reached
+/// # Errors
+/// coming from [`importer::validate_input`]
+#[tauri::command]
+fn validate_input(options: ImportOptions) -> Result<InputValidation, String> {
+    importer::validate_input(&options).map_err(|e| e.to_string())
+}
+
+/// save the built-in [mapping profile](backboard::mapping::MappingProfile) to `path`, as a starting point for custom ones
+/// # Errors
+/// can't write to `path`
+#[tauri::command]
+fn export_default_mapping_profile(path: String) -> Result<(), String> {
+    log::info!("exporting default mapping profile to {path:?}");
+    std::fs::write(path, backboard::mapping::DEFAULT_PROFILE).map_err(|e| e.to_string())
+}
+
+/// past import runs matching the `filter`, the most recent first
+/// # Errors
+/// can't read the [import history](backboard::history)
+#[tauri::command]
+fn list_import_history(window: Window, filter: HistoryFilter) -> Result<Vec<HistoryEntry>, String> {
+    History::open(history_path(&window)?)
+        .and_then(|history| history.list(&filter))
+        .map_err(|e| e.to_string())
+}
 
-    Ok(())
+/// save the `report` of an import to `path`, as json if it ends with `.json`, as csv otherwise
+/// # Errors
+/// can't write to `path`
+#[tauri::command]
+fn export_import_report(report: ImportReport, path: String) -> Result<(), String> {
+    report.export(path)
+}
+
+/// [dry-run](importer::preview_import) of [`import_grades`], nothing gets uploaded
+/// # Errors
+/// coming from [`importer::preview_import`]
+#[tauri::command]
+async fn preview_import(
+    window: Window,
+    mut options: ImportOptions,
+) -> Result<ImportPreview, String> {
+    options.fingerprints_path = fingerprints_path(&window);
+    importer::preview_import(options, report_to_window(&window, "import"))
+        .await
+        .map_err(|e| e.to_string())
 }
 
 /// GET status of server
@@ -186,14 +231,19 @@ async fn status(blueboard_url: String) -> Result<StatusViewServiceStatusResponse
 }
 
 fn main() {
-    let log_p = std::path::Path::new(".lovassyapp-backboard.log");
-    ftail::Ftail::new()
-        .console_env_level()
-        .single_file_env_level(log_p, true)
-        .init()
-        .unwrap(); // logs to `stderr` and file at runtime dir as well
+    let unsafe_debug =
+        std::env::var(redact::UNSAFE_DEBUG_ENV).is_ok_and(|v| v == "1" || v == "true");
 
     tauri::Builder::default()
+        .setup(move |app| {
+            // the working dir of the GUI may not be writable, see `app_data_path`
+            let log_dir = app.path().app_log_dir();
+            backboard::init_logger(unsafe_debug, log_dir.as_deref().ok());
+            if let Err(e) = log_dir {
+                log::warn!("logging to the console only, no app log dir: {e}");
+            }
+            Ok(())
+        })
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .plugin(tauri_plugin_autostart::Builder::new().build())
         .plugin(tauri_plugin_dialog::init())
@@ -203,10 +253,21 @@ fn main() {
             None,
         ))
         .plugin(tauri_plugin_store::Builder::default().build())
+        .manage(ImportCancellation::default())
         .invoke_handler(tauri::generate_handler![
             status,
             upload_reset_key_password,
-            import_grades
+            import_grades,
+            cancel_import,
+            sync_roster,
+            preview_import,
+            resumable_import,
+            validate_input,
+            export_import_report,
+            check_coverage,
+            export_coverage_report,
+            export_default_mapping_profile,
+            list_import_history
         ])
         .run(tauri::generate_context!())
         .expect("encountered an unexpected, fatal error while running Tauri application");
@@ -0,0 +1,199 @@
+//! # Mapping
+//! maps the column headers of the E-Kreta exports to the fields of
+//! [`BackboardGrade`](crate::grades_processor::BackboardGrade) and [`BackboardStudent`](crate::grades_processor::BackboardStudent)\
+//! so if E-Kreta renames a column, only a profile has to be edited, not the code\
+//! the [built-in profile](DEFAULT_PROFILE) matches the current exports, others can be loaded from json or toml files
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+/// the built-in profile, matching the current E-Kreta exports, as toml
+pub const DEFAULT_PROFILE: &str = include_str!("../mapping_profiles/kreta.toml");
+
+/// a field of the parsed rows, mapped to a column of the export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Column<'a> {
+    /// name of the field when deserializing
+    pub field: &'static str,
+    /// header of the column in the export
+    pub header: &'a str,
+    /// rows can't be parsed without this column
+    pub required: bool,
+}
+impl<'a> Column<'a> {
+    fn new(field: &'static str, header: &'a str, required: bool) -> Self {
+        Self {
+            field,
+            header,
+            required,
+        }
+    }
+}
+
+/// source headers of the grades export
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct GradeColumns {
+    pub student_name: String,
+    pub school_class: String,
+    pub om_code: String,
+    pub subject_category: String,
+    pub subject: String,
+    pub group: String,
+    pub teacher: String,
+    pub theme: String,
+    pub r#type: String,
+    pub text_grade: String,
+    pub grade: String,
+    pub short_text_grade: String,
+    pub behavior_grade: String,
+    pub diligence_grade: String,
+    pub create_date: String,
+    pub record_date: String,
+}
+impl GradeColumns {
+    pub fn columns(&self) -> [Column<'_>; 16] {
+        let column = Column::new;
+        [
+            column("StudentName", &self.student_name, true),
+            column("SchoolClass", &self.school_class, false),
+            column("OmCode", &self.om_code, true),
+            column("SubjectCategory", &self.subject_category, true),
+            column("Subject", &self.subject, true),
+            column("Group", &self.group, true),
+            column("Teacher", &self.teacher, false),
+            column("Theme", &self.theme, true),
+            column("Type", &self.r#type, false),
+            column("TextGrade", &self.text_grade, true),
+            column("Grade", &self.grade, false),
+            column("ShortTextGrade", &self.short_text_grade, true),
+            column("BehaviorGrade", &self.behavior_grade, true),
+            column("DiligenceGrade", &self.diligence_grade, true),
+            column("CreateDate", &self.create_date, true),
+            column("RecordDate", &self.record_date, true),
+        ]
+    }
+}
+
+/// source headers of the students export
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct StudentColumns {
+    pub name: String,
+    pub om_code: String,
+    pub class: String,
+}
+impl StudentColumns {
+    pub fn columns(&self) -> [Column<'_>; 3] {
+        [
+            Column::new("Name", &self.name, true),
+            Column::new("OmCode", &self.om_code, true),
+            Column::new("Class", &self.class, true),
+        ]
+    }
+}
+
+/// couldn't load a mapping profile
+#[derive(Debug)]
+pub enum ProfileError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Toml(toml::de::Error),
+}
+impl fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "invalid mapping profile: {err}"),
+            Self::Json(err) => write!(f, "invalid mapping profile: {err}"),
+            Self::Toml(err) => write!(f, "invalid mapping profile: {err}"),
+        }
+    }
+}
+impl std::error::Error for ProfileError {}
+
+/// which column of the exports holds which field
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct MappingProfile {
+    pub name: String,
+    pub grades: GradeColumns,
+    pub students: StudentColumns,
+}
+impl Default for MappingProfile {
+    /// the [built-in profile](DEFAULT_PROFILE)
+    fn default() -> Self {
+        toml::from_str(DEFAULT_PROFILE).expect("built-in mapping profile is valid")
+    }
+}
+impl MappingProfile {
+    /// load the profile at `path`, json if it has a `.json` extension, toml otherwise
+    /// # Errors
+    /// can't read `path`, invalid json or toml
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ProfileError> {
+        let path = path.as_ref();
+        log::info!("loading mapping profile from {path:?}");
+        let text = std::fs::read_to_string(path).map_err(ProfileError::Io)?;
+        let profile: Self = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&text).map_err(ProfileError::Json)?
+        } else {
+            toml::from_str(&text).map_err(ProfileError::Toml)?
+        };
+        log::info!("using mapping profile {:?}", profile.name);
+        Ok(profile)
+    }
+
+    /// load the profile at `path` if given, the [built-in one](DEFAULT_PROFILE) otherwise
+    /// # Errors
+    /// coming from [`MappingProfile::load`]
+    pub fn load_or_default(path: Option<&str>) -> Result<Self, ProfileError> {
+        path.map_or_else(|| Ok(Self::default()), Self::load)
+    }
+}
+
+#[test]
+fn load_profiles() {
+    let default = MappingProfile::default();
+    assert_eq!(default.grades.subject, "Tantárgy");
+    assert!(
+        default
+            .grades
+            .columns()
+            .iter()
+            .all(|c| !c.header.is_empty())
+    );
+
+    let path = std::env::temp_dir().join("backboard-test-profile.json");
+    let mut renamed = default.clone();
+    renamed.grades.subject = String::from("Tárgy");
+    std::fs::write(&path, serde_json::to_string(&renamed).unwrap()).unwrap();
+    assert_eq!(MappingProfile::load(&path).unwrap(), renamed);
+
+    std::fs::write(&path, r#"{"name": "incomplete"}"#).unwrap();
+    assert!(matches!(
+        MappingProfile::load(&path),
+        Err(ProfileError::Json(_))
+    ));
+}
+
+#[test]
+fn parse_renamed_columns() {
+    use crate::grades_processor::{InputError, fixtures, process_grades_file};
+
+    // E-Kreta renamed "Tantárgy" to "Tárgy"
+    let row = std::fs::read_to_string("test_grades.csv").unwrap();
+    let row = row.lines().nth(1).unwrap();
+    let header = fixtures::GRADES_HEADER.replace(";Tantárgy;", ";Tárgy;");
+    let path = fixtures::write_csv("renamed-columns", &header, &[row]);
+    assert!(matches!(
+        process_grades_file(path.clone(), &MappingProfile::default(), &Default::default()),
+        Err(InputError::MissingColumns(missing)) if missing == ["Tantárgy"]
+    ));
+
+    let mut profile = MappingProfile::default();
+    profile.grades.subject = String::from("Tárgy");
+    let grades = process_grades_file(path, &profile, &Default::default())
+        .unwrap()
+        .data;
+    assert_eq!(grades, fixtures::grades());
+}
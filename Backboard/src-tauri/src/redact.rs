@@ -0,0 +1,61 @@
+//! # Redact
+//! keeps personal data (names, classes, grades, keys, om codes) out of the logs, at every level\
+//! such values are logged wrapped in [`Redacted`], printing only a pseudonym, unless [unsafe debug](set_unsafe_debug) is on
+//!
+//! the pseudonyms are salted with a random value per run, so they can't be looked up,
+//! but the same value gets the same pseudonym within a run, so the log lines can still be followed
+
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// setting this environment variable turns on [unsafe debug](set_unsafe_debug) for the *GUI*
+pub const UNSAFE_DEBUG_ENV: &str = "BACKBOARD_UNSAFE_DEBUG";
+
+static UNSAFE_DEBUG: AtomicBool = AtomicBool::new(false);
+
+/// log personal data as is, only for debugging on a machine the log file never leaves
+pub fn set_unsafe_debug(unsafe_debug: bool) {
+    if unsafe_debug {
+        log::warn!("unsafe debug is on, personal data gets logged unredacted");
+    }
+    UNSAFE_DEBUG.store(unsafe_debug, Ordering::Relaxed);
+}
+
+/// whether [`Redacted`] values are logged as is
+pub fn unsafe_debug() -> bool {
+    UNSAFE_DEBUG.load(Ordering::Relaxed)
+}
+
+/// short, salted digest of `value`, the same throughout a run
+pub fn pseudonym(value: &str) -> String {
+    static SALT: OnceLock<[u8; 16]> = OnceLock::new();
+    let mut hasher = Sha256::new();
+    hasher.update(SALT.get_or_init(rand::random));
+    hasher.update(value);
+    let digest = hasher.finalize();
+    digest[..4].iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// personal data to be logged, its [`Debug`](fmt::Debug) output is replaced with a [`pseudonym`]
+/// unless [unsafe debug](set_unsafe_debug) is on
+pub struct Redacted<T>(pub T);
+impl<T: fmt::Debug> fmt::Debug for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if unsafe_debug() {
+            return self.0.fmt(f);
+        }
+        write!(f, "<redacted {}>", pseudonym(&format!("{:?}", self.0)))
+    }
+}
+
+#[test]
+fn redact_personal_data() {
+    let name = Redacted("Aladar Barnabas");
+    let redacted = format!("{name:?}");
+    assert!(redacted.starts_with("<redacted "));
+    assert!(!redacted.contains("Aladar"));
+    assert_eq!(redacted, format!("{:?}", Redacted("Aladar Barnabas")));
+    assert_ne!(redacted, format!("{:?}", Redacted("Buga Matyi")));
+}
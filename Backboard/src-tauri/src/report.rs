@@ -0,0 +1,216 @@
+//! # Report
+//! per-user outcome of an import, so it's known exactly who got their grades and who didn't\
+//! can be exported to csv or json to be handed over to IT
+
+use api::models::ProblemDetails;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// what happened to a single user during the import
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum UserOutcome {
+    /// grades successfully posted
+    Success,
+    /// no imported grades for the user, nothing to post
+    Skipped,
+    /// already posted by an earlier, interrupted run of the same import
+    AlreadyImported,
+    /// the very same data was posted the last time, see [`fingerprints`](crate::fingerprints)
+    Unchanged,
+    /// left out of a [roster sync](crate::importer::sync_roster), as grades were posted to the user before,
+    /// which would be lost if they haven't logged in since
+    PendingGrades,
+    /// not even tried, as the import got cancelled
+    Cancelled,
+    /// the user record coming from the server is malformed, see [`UserRecordError`](crate::grades_processor::UserRecordError)
+    InvalidRecord { message: String },
+    /// the grade collection couldn't be encrypted with the user's public key\
+    /// `cause` is the [kind](crate::cryptography::CryptoError::kind) of the error, missing from reports of earlier versions
+    EncryptionError {
+        #[serde(default)]
+        cause: Option<String>,
+        message: String,
+    },
+    /// the POST failed, even after retrying\
+    /// `status` is missing if the server couldn't be reached at all
+    HttpError {
+        status: Option<u16>,
+        message: String,
+        problem: Option<ProblemDetails>,
+    },
+}
+impl UserOutcome {
+    pub fn is_failure(&self) -> bool {
+        matches!(
+            self,
+            Self::InvalidRecord { .. } | Self::EncryptionError { .. } | Self::HttpError { .. }
+        )
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::Skipped => "skipped",
+            Self::AlreadyImported => "alreadyImported",
+            Self::Unchanged => "unchanged",
+            Self::PendingGrades => "pendingGrades",
+            Self::Cancelled => "cancelled",
+            Self::InvalidRecord { .. } => "invalidRecord",
+            Self::EncryptionError { .. } => "encryptionError",
+            Self::HttpError { .. } => "httpError",
+        }
+    }
+
+    /// human readable details of the failure, preferring the server's own words
+    fn message(&self) -> Option<String> {
+        match self {
+            Self::Success
+            | Self::Skipped
+            | Self::AlreadyImported
+            | Self::Unchanged
+            | Self::PendingGrades
+            | Self::Cancelled => None,
+            Self::InvalidRecord { message } | Self::EncryptionError { message, .. } => {
+                Some(message.clone())
+            }
+            Self::HttpError {
+                message, problem, ..
+            } => {
+                let problem = problem.as_ref().and_then(|p| {
+                    let parts = [p.title.clone().flatten(), p.detail.clone().flatten()];
+                    let parts = parts.into_iter().flatten().collect::<Vec<_>>();
+                    (!parts.is_empty()).then(|| parts.join(": "))
+                });
+                Some(problem.unwrap_or_else(|| message.clone()))
+            }
+        }
+    }
+}
+
+/// outcome of the import for one registered user
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserImportResult {
+    pub user_id: String,
+    /// unknown for skipped users
+    pub student_name: Option<String>,
+    pub school_class: Option<String>,
+    pub outcome: UserOutcome,
+}
+
+/// one row of the csv export
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CsvRow<'a> {
+    user_id: &'a str,
+    student_name: Option<&'a str>,
+    school_class: Option<&'a str>,
+    outcome: &'static str,
+    http_status: Option<u16>,
+    message: Option<String>,
+}
+
+/// per-user outcome of an [import](crate::importer::import_grades)
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub results: Vec<UserImportResult>,
+}
+impl ImportReport {
+    pub fn num_failed(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|r| r.outcome.is_failure())
+            .count()
+    }
+
+    /// write the report to `path` as csv, using the ';' character as delimiter, like E-Kreta does
+    /// # Errors
+    /// can't write to `path`
+    pub fn write_csv(&self, path: impl AsRef<Path>) -> Result<(), csv::Error> {
+        let mut writer = csv::WriterBuilder::new().delimiter(b';').from_path(path)?;
+        for result in &self.results {
+            writer.serialize(CsvRow {
+                user_id: &result.user_id,
+                student_name: result.student_name.as_deref(),
+                school_class: result.school_class.as_deref(),
+                outcome: result.outcome.kind(),
+                http_status: match result.outcome {
+                    UserOutcome::HttpError { status, .. } => status,
+                    _ => None,
+                },
+                message: result.outcome.message(),
+            })?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// write the report to `path` as pretty json
+    /// # Errors
+    /// can't write to `path`
+    pub fn write_json(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    /// write the report to `path`, as json if it has a `.json` extension, as csv otherwise
+    /// # Errors
+    /// can't write to `path`
+    pub fn export(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        log::info!("exporting import report to {path:?}");
+        if path.extension().is_some_and(|ext| ext == "json") {
+            self.write_json(path).map_err(|e| e.to_string())
+        } else {
+            self.write_csv(path).map_err(|e| e.to_string())
+        }
+    }
+}
+
+#[test]
+fn export_report() {
+    let report = ImportReport {
+        results: vec![
+            UserImportResult {
+                user_id: String::from("a"),
+                student_name: Some(String::from("Aladar Barnabas")),
+                school_class: Some(String::from("10.C")),
+                outcome: UserOutcome::Success,
+            },
+            UserImportResult {
+                user_id: String::from("b"),
+                student_name: None,
+                school_class: None,
+                outcome: UserOutcome::HttpError {
+                    status: Some(404),
+                    message: String::from("404"),
+                    problem: Some(ProblemDetails {
+                        title: Some(Some(String::from("Not Found"))),
+                        ..ProblemDetails::new()
+                    }),
+                },
+            },
+        ],
+    };
+    assert_eq!(report.num_failed(), 1);
+
+    let dir = std::env::temp_dir();
+    let csv_path = dir.join("backboard-test-report.csv");
+    report.export(&csv_path).unwrap();
+    let csv = std::fs::read_to_string(&csv_path).unwrap();
+    assert_eq!(
+        csv,
+        "userId;studentName;schoolClass;outcome;httpStatus;message\n\
+         a;Aladar Barnabas;10.C;success;;\n\
+         b;;;httpError;404;Not Found\n"
+    );
+
+    let json_path = dir.join("backboard-test-report.json");
+    report.export(&json_path).unwrap();
+    let json = std::fs::read_to_string(&json_path).unwrap();
+    let parsed: ImportReport = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed.results[1].outcome, report.results[1].outcome);
+}
@@ -0,0 +1,195 @@
+//! # Sniffer
+//! guesses how a csv file was written: E-Kreta exports use ';' and UTF-8,
+//! but once opened and re-saved in Excel, they may come back comma-separated, as Windows-1250 or UTF-16
+
+use encoding_rs::{Encoding, UTF_8, UTF_16BE, UTF_16LE, WINDOWS_1250};
+use serde::Serialize;
+
+/// delimiters worth considering, the first one wins ties
+const DELIMITERS: [u8; 3] = [b';', b',', b'\t'];
+/// bytes looked at when guessing whether there's UTF-16 without a BOM
+const UTF16_SAMPLE_LEN: usize = 1024;
+
+/// text encoding of a csv file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TextEncoding {
+    Utf8,
+    /// UTF-8, starting with a byte order mark, as saved by Excel
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+    /// legacy Central European code page of Windows
+    Windows1250,
+}
+impl TextEncoding {
+    fn encoding(self) -> &'static Encoding {
+        match self {
+            Self::Utf8 | Self::Utf8Bom => UTF_8,
+            Self::Utf16Le => UTF_16LE,
+            Self::Utf16Be => UTF_16BE,
+            Self::Windows1250 => WINDOWS_1250,
+        }
+    }
+}
+impl std::fmt::Display for TextEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Utf8 => "UTF-8",
+            Self::Utf8Bom => "UTF-8 with BOM",
+            Self::Utf16Le => "UTF-16LE",
+            Self::Utf16Be => "UTF-16BE",
+            Self::Windows1250 => "Windows-1250",
+        })
+    }
+}
+
+/// how a csv file was written, see [`sniff`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvFormat {
+    pub delimiter: char,
+    pub encoding: TextEncoding,
+}
+
+/// UTF-16 without a BOM, if most of the code units of `bytes` have a zero byte, on the same side\
+/// a csv has no NUL characters, but mostly Latin ones, so this holds even if it's valid UTF-8 (ASCII only)
+fn sniff_utf16(bytes: &[u8]) -> Option<TextEncoding> {
+    if !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    let sample = &bytes[..bytes.len().min(UTF16_SAMPLE_LEN)];
+    let num_units = sample.len() / 2;
+    let zeros_at = |offset| {
+        sample
+            .iter()
+            .skip(offset)
+            .step_by(2)
+            .filter(|b| **b == 0)
+            .count()
+    };
+    if zeros_at(1) * 2 > num_units {
+        Some(TextEncoding::Utf16Le)
+    } else if zeros_at(0) * 2 > num_units {
+        Some(TextEncoding::Utf16Be)
+    } else {
+        None
+    }
+}
+
+/// guess the encoding of `bytes`: by its BOM if there's one, otherwise UTF-16 if every other byte is zero,
+/// UTF-8 if valid, and Windows-1250 as a last resort
+fn sniff_encoding(bytes: &[u8]) -> TextEncoding {
+    match Encoding::for_bom(bytes) {
+        Some((encoding, _)) if encoding == UTF_8 => return TextEncoding::Utf8Bom,
+        Some((encoding, _)) if encoding == UTF_16LE => return TextEncoding::Utf16Le,
+        Some((encoding, _)) if encoding == UTF_16BE => return TextEncoding::Utf16Be,
+        _ => {}
+    }
+    if let Some(encoding) = sniff_utf16(bytes) {
+        encoding
+    } else if std::str::from_utf8(bytes).is_ok() {
+        TextEncoding::Utf8
+    } else {
+        TextEncoding::Windows1250
+    }
+}
+
+/// the delimiter occurring the most in the header line, outside of quotes, ';' if none of them does
+fn sniff_delimiter(text: &str) -> u8 {
+    let header = text.lines().next().unwrap_or_default();
+    let mut counts = [0; DELIMITERS.len()];
+    let mut quoted = false;
+    for b in header.bytes() {
+        if b == b'"' {
+            quoted = !quoted;
+        } else if !quoted && let Some(i) = DELIMITERS.iter().position(|d| *d == b) {
+            counts[i] += 1;
+        }
+    }
+    let (i, count) = counts
+        .iter()
+        .enumerate()
+        .rev() // max_by_key returns the last max
+        .max_by_key(|(_, count)| **count)
+        .unwrap_or((0, &0));
+    if *count == 0 {
+        DELIMITERS[0]
+    } else {
+        DELIMITERS[i]
+    }
+}
+
+/// guess the encoding and delimiter of the csv in `bytes`, then decode it, without the BOM
+pub fn sniff(bytes: &[u8]) -> (String, CsvFormat) {
+    let encoding = sniff_encoding(bytes);
+    let (text, _) = encoding.encoding().decode_with_bom_removal(bytes);
+    let delimiter = sniff_delimiter(&text);
+    let format = CsvFormat {
+        delimiter: char::from(delimiter),
+        encoding,
+    };
+    log::info!("sniffed csv format: {format:?}");
+    (text.into_owned(), format)
+}
+
+#[test]
+fn sniff_excel_exports() {
+    let csv = "Név;Osztály\nBuga Matyi;9.A\n";
+    assert_eq!(
+        sniff(csv.as_bytes()),
+        (
+            csv.to_owned(),
+            CsvFormat {
+                delimiter: ';',
+                encoding: TextEncoding::Utf8
+            }
+        )
+    );
+
+    let comma = "\u{feff}\"Név;2\",Osztály\n";
+    let (text, format) = sniff(comma.as_bytes());
+    assert_eq!(text, &comma[3..]);
+    assert_eq!(format.delimiter, ',');
+    assert_eq!(format.encoding, TextEncoding::Utf8Bom);
+
+    let (bytes, _, _) = WINDOWS_1250.encode(csv);
+    let (text, format) = sniff(&bytes);
+    assert_eq!(
+        (text.as_str(), format.encoding),
+        (csv, TextEncoding::Windows1250)
+    );
+
+    let utf16 = csv
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes)
+        .collect::<Vec<_>>();
+    let (text, format) = sniff(&utf16);
+    assert_eq!(
+        (text.as_str(), format.encoding),
+        (csv, TextEncoding::Utf16Le)
+    );
+    let with_bom = [&[0xff, 0xfe], utf16.as_slice()].concat();
+    assert_eq!(sniff(&with_bom), (text, format));
+
+    // Excel's "Unicode text" without a BOM, ASCII only, so it's valid UTF-8 as well
+    let ascii = "Nev\tOsztaly\nBuga Matyi\t9.A\n";
+    for (bytes, encoding) in [
+        (
+            ascii
+                .encode_utf16()
+                .flat_map(u16::to_le_bytes)
+                .collect::<Vec<_>>(),
+            TextEncoding::Utf16Le,
+        ),
+        (
+            ascii.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+            TextEncoding::Utf16Be,
+        ),
+    ] {
+        assert!(std::str::from_utf8(&bytes).is_ok());
+        let (text, format) = sniff(&bytes);
+        assert_eq!((text.as_str(), format.encoding), (ascii, encoding));
+        assert_eq!(format.delimiter, '\t');
+    }
+}
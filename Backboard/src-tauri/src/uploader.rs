@@ -0,0 +1,256 @@
+//! # Uploader
+//! posts the encrypted grade collections to the server, retrying when it's overwhelmed\
+//! mirrors [`api_import_grades_user_id_post`](api::apis::import_api::api_import_grades_user_id_post),
+//! but keeps the response headers, as the generated bindings drop `Retry-After`
+
+use crate::report::UserOutcome;
+use api::apis::configuration::Configuration;
+use api::models::{ImportImportGradesRequestBody, ProblemDetails};
+use reqwest::StatusCode;
+use reqwest::header::{HeaderMap, RETRY_AFTER, USER_AGENT};
+use std::fmt;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// number of users whose grades are being encrypted and posted at the same time
+pub const MAX_CONCURRENT_UPLOADS: usize = 4;
+/// attempts of a single POST before giving up on the user
+const MAX_ATTEMPTS: u32 = 5;
+/// waited before the first retry, doubled on each further one, unless the server says otherwise
+const BASE_BACKOFF: Duration = Duration::from_millis(500);
+/// upper bound of any wait, even if `Retry-After` asks for more
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// a failed POST
+#[derive(Debug, Clone)]
+pub struct UploadError {
+    /// missing if the server couldn't be reached at all
+    pub status: Option<u16>,
+    pub message: String,
+    /// body of the error response, if the server sent one
+    pub problem: Option<ProblemDetails>,
+}
+impl fmt::Display for UploadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+impl From<UploadError> for UserOutcome {
+    fn from(e: UploadError) -> Self {
+        Self::HttpError {
+            status: e.status,
+            message: e.message,
+            problem: e.problem,
+        }
+    }
+}
+
+/// the POST of a user's data gave up
+#[derive(Debug, Clone)]
+pub enum PostError {
+    Failed(UploadError),
+    /// cancelled while waiting to retry
+    Cancelled,
+}
+impl fmt::Display for PostError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Failed(e) => e.fmt(f),
+            Self::Cancelled => write!(f, "cancelled while waiting to retry"),
+        }
+    }
+}
+impl From<PostError> for UserOutcome {
+    fn from(e: PostError) -> Self {
+        match e {
+            PostError::Failed(e) => e.into(),
+            PostError::Cancelled => Self::Cancelled,
+        }
+    }
+}
+
+/// outcome of a single POST attempt
+enum Attempt {
+    /// worth trying again after waiting, the server may have told how much
+    Retry {
+        error: UploadError,
+        retry_after: Option<Duration>,
+    },
+    /// no point in trying again
+    Fail(UploadError),
+}
+
+/// `Retry-After` header in seconds, the http-date form isn't sent by the server
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let secs = headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(Duration::from_secs(secs))
+}
+
+/// time to wait before the `attempt`. (counted from 1) retry
+fn backoff(attempt: u32, retry_after: Option<Duration>) -> Duration {
+    retry_after
+        .unwrap_or_else(|| BASE_BACKOFF.saturating_mul(2u32.saturating_pow(attempt - 1)))
+        .min(MAX_BACKOFF)
+}
+
+async fn post_once(
+    config: &Configuration,
+    user_id: &str,
+    json_encrypted: &str,
+) -> Result<(), Attempt> {
+    let uri_str = format!(
+        "{}/Api/Import/Grades/{}",
+        config.base_path,
+        api::apis::urlencode(user_id)
+    );
+    let mut req_builder = config.client.post(&uri_str);
+    if let Some(user_agent) = &config.user_agent {
+        req_builder = req_builder.header(USER_AGENT, user_agent);
+    }
+    if let Some(apikey) = &config.api_key {
+        let value = match &apikey.prefix {
+            Some(prefix) => format!("{prefix} {}", apikey.key),
+            None => apikey.key.clone(),
+        };
+        req_builder = req_builder.header("X-Authorization", value);
+    }
+    let body = ImportImportGradesRequestBody {
+        json_encrypted: json_encrypted.to_owned(),
+    };
+
+    let resp = match req_builder.json(&body).send().await {
+        Ok(resp) => resp,
+        Err(e) => {
+            let error = UploadError {
+                status: None,
+                message: e.to_string(),
+                problem: None,
+            };
+            return Err(if e.is_timeout() || e.is_connect() {
+                Attempt::Retry {
+                    error,
+                    retry_after: None,
+                }
+            } else {
+                Attempt::Fail(error)
+            });
+        }
+    };
+
+    let status = resp.status();
+    if !status.is_client_error() && !status.is_server_error() {
+        return Ok(());
+    }
+    let retry_after = retry_after(resp.headers());
+    let content = resp.text().await.unwrap_or_default();
+    log::error!("error: status {status}: {content}");
+
+    let error = UploadError {
+        status: Some(status.as_u16()),
+        message: status.to_string(),
+        problem: serde_json::from_str(&content).ok(),
+    };
+    if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+        Err(Attempt::Retry { error, retry_after })
+    } else {
+        Err(Attempt::Fail(error))
+    }
+}
+
+/// POST the `json_encrypted` grade collection of the user with `user_id`\
+/// on `429`, `5xx` and connection errors, backs off and retries, honouring `Retry-After`, unless `cancel`led
+/// # Errors
+/// - the last error, if the request didn't succeed after `MAX_ATTEMPTS` attempts or isn't worth retrying
+/// - `cancel`led while waiting to retry
+pub async fn post_grades(
+    config: &Configuration,
+    user_id: &str,
+    json_encrypted: String,
+    cancel: &CancellationToken,
+) -> Result<(), PostError> {
+    let mut attempt = 1;
+    loop {
+        match post_once(config, user_id, &json_encrypted).await {
+            Ok(()) => return Ok(()),
+            Err(Attempt::Fail(error)) => return Err(PostError::Failed(error)),
+            Err(Attempt::Retry { error, .. }) if attempt == MAX_ATTEMPTS => {
+                return Err(PostError::Failed(error));
+            }
+            Err(Attempt::Retry { error, retry_after }) => {
+                let wait = backoff(attempt, retry_after);
+                log::warn!("posting failed ({error}), retrying in {wait:?}");
+                tokio::select! {
+                    () = tokio::time::sleep(wait) => {}
+                    () = cancel.cancelled() => return Err(PostError::Cancelled),
+                }
+                attempt += 1;
+            }
+        }
+    }
+}
+
+#[test]
+fn backoff_honours_retry_after() {
+    let mut headers = HeaderMap::new();
+    assert_eq!(retry_after(&headers), None);
+    headers.insert(RETRY_AFTER, "7".parse().unwrap());
+    assert_eq!(retry_after(&headers), Some(Duration::from_secs(7)));
+
+    assert_eq!(backoff(1, None), BASE_BACKOFF);
+    assert_eq!(backoff(3, None), BASE_BACKOFF * 4);
+    assert_eq!(backoff(30, None), MAX_BACKOFF);
+    assert_eq!(
+        backoff(1, Some(Duration::from_secs(7))),
+        Duration::from_secs(7)
+    );
+    assert_eq!(backoff(1, Some(Duration::from_secs(3600))), MAX_BACKOFF);
+}
+
+#[test]
+fn cancel_while_waiting_to_retry() {
+    use std::io::{Read, Write};
+
+    // a server that's always overwhelmed, asking to come back in a minute
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut config = Configuration::new();
+    config.base_path = format!("http://{}", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let _ = stream.read(&mut [0; 4096]);
+            let _ = stream.write_all(
+                b"HTTP/1.1 429 Too Many Requests\r\nRetry-After: 60\r\nContent-Length: 0\r\n\r\n",
+            );
+        }
+    });
+
+    let cancel = CancellationToken::new();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let result = runtime.block_on(async {
+        let canceller = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            canceller.cancel();
+        });
+        tokio::time::timeout(
+            Duration::from_secs(10),
+            post_grades(&config, "user", String::from("data"), &cancel),
+        )
+        .await
+        .expect("cancelling stops the wait")
+    });
+    assert!(matches!(result, Err(PostError::Cancelled)));
+    assert_eq!(
+        UserOutcome::from(result.unwrap_err()),
+        UserOutcome::Cancelled
+    );
+}
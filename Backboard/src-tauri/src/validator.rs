@@ -0,0 +1,265 @@
+//! # Validator
+//! checks the rows of the input files one by one before they get parsed,
+//! so a single malformed row out of thousands doesn't stop the import\
+//! every problem is collected with its line number, column header and offending value
+
+use chrono::NaiveDate;
+use csv::StringRecord;
+use serde::Serialize;
+use std::fmt;
+
+// fields checked, the headers of their columns are set by the mapping profile
+const OM_CODE_FIELD: &str = "OmCode";
+const SUBJECT_FIELD: &str = "Subject";
+const SUBJECT_CATEGORY_FIELD: &str = "SubjectCategory";
+const THEME_FIELD: &str = "Theme";
+const GRADE_FIELD: &str = "Grade";
+const DATE_FIELDS: [&str; 2] = ["CreateDate", "RecordDate"];
+
+/// what's wrong with a value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum RowProblem {
+    MissingOmCode,
+    /// not like `2025.09.03.`
+    InvalidDate,
+    /// "Jegy" isn't a whole number from 1 to 5
+    GradeOutOfRange,
+    EmptySubject,
+    /// the server capitalises it by its first character
+    EmptySubjectCategory,
+    /// E-Kreta's placeholder is fine, an empty cell crashes the server
+    EmptyTheme,
+    /// the row doesn't fit the expected columns at all
+    Malformed {
+        message: String,
+    },
+}
+impl fmt::Display for RowProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingOmCode => write!(f, "missing om code"),
+            Self::InvalidDate => write!(f, "invalid date"),
+            Self::GradeOutOfRange => write!(f, "grade outside 1-5"),
+            Self::EmptySubject => write!(f, "empty subject"),
+            Self::EmptySubjectCategory => write!(f, "empty subject category"),
+            Self::EmptyTheme => write!(f, "empty theme"),
+            Self::Malformed { message } => write!(f, "{message}"),
+        }
+    }
+}
+
+/// a problem found in a row of an input file, that row gets left out of the import
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RowIssue {
+    /// name of the input file, if there are several of the same kind
+    pub file: Option<String>,
+    /// counted from 1, the header being the first line
+    pub line: u64,
+    /// header of the offending column, if known
+    pub column: Option<String>,
+    pub value: Option<String>,
+    pub problem: RowProblem,
+}
+impl fmt::Display for RowIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{file}, ")?;
+        }
+        write!(f, "line {}", self.line)?;
+        if let Some(column) = &self.column {
+            write!(f, ", {column:?}")?;
+        }
+        if let Some(value) = &self.value {
+            write!(f, " = {value:?}")?;
+        }
+        write!(f, ": {}", self.problem)
+    }
+}
+
+/// a row of an input file, its values looked up by field
+pub struct Row<'a> {
+    pub line: u64,
+    /// names of the fields the columns are mapped to
+    pub headers: &'a StringRecord,
+    /// headers as they are in the file, used when reporting
+    pub source_headers: &'a StringRecord,
+    pub record: &'a StringRecord,
+}
+impl Row<'_> {
+    /// index and value of the `field`, `None` if it's not mapped to any column
+    fn get(&self, field: &str) -> Option<(usize, &str)> {
+        let i = self.headers.iter().position(|header| header == field)?;
+        Some((i, self.record.get(i).unwrap_or_default()))
+    }
+
+    fn issue(&self, (i, value): (usize, &str), problem: RowProblem) -> RowIssue {
+        RowIssue {
+            file: None,
+            line: self.line,
+            column: self.source_headers.get(i).map(str::to_owned),
+            value: Some(value.to_owned()),
+            problem,
+        }
+    }
+
+    /// the row couldn't be deserialized, `field` being the index of the offending column
+    pub fn malformed(&self, field: Option<u64>, message: String) -> RowIssue {
+        let column = field.and_then(|i| self.source_headers.get(usize::try_from(i).ok()?));
+        let value = field.and_then(|i| self.record.get(usize::try_from(i).ok()?));
+        RowIssue {
+            file: None,
+            line: self.line,
+            column: column.map(str::to_owned),
+            value: value.map(str::to_owned),
+            problem: RowProblem::Malformed { message },
+        }
+    }
+}
+
+/// parse a date of the E-Kreta export, like `2025.09.03.`
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
+    let value = value.trim();
+    NaiveDate::parse_from_str(value.strip_suffix('.').unwrap_or(value), "%Y.%m.%d").ok()
+}
+
+/// every problem of a row of the grades export\
+/// missing columns aren't checked here, deserialization complains about them anyway
+pub fn validate_grade(row: &Row) -> Vec<RowIssue> {
+    let mut issues = Vec::new();
+    if let Some(om_code) = row.get(OM_CODE_FIELD)
+        && om_code.1.trim().is_empty()
+    {
+        issues.push(row.issue(om_code, RowProblem::MissingOmCode));
+    }
+    for (field, problem) in [
+        (SUBJECT_FIELD, RowProblem::EmptySubject),
+        (SUBJECT_CATEGORY_FIELD, RowProblem::EmptySubjectCategory),
+        (THEME_FIELD, RowProblem::EmptyTheme),
+    ] {
+        if let Some(value) = row.get(field)
+            && value.1.trim().is_empty()
+        {
+            issues.push(row.issue(value, problem));
+        }
+    }
+    for field in DATE_FIELDS {
+        if let Some(date) = row.get(field)
+            && parse_date(date.1).is_none()
+        {
+            issues.push(row.issue(date, RowProblem::InvalidDate));
+        }
+    }
+    // text-only evaluations have no grade at all, it's sent as `null` then
+    if let Some(grade) = row.get(GRADE_FIELD)
+        && !matches!(grade.1.trim(), "" | "-")
+        && !grade
+            .1
+            .trim()
+            .parse()
+            .is_ok_and(|g: u8| (1..=5).contains(&g))
+    {
+        issues.push(row.issue(grade, RowProblem::GradeOutOfRange));
+    }
+    issues
+}
+
+/// every problem of a row of the students export
+pub fn validate_student(row: &Row) -> Vec<RowIssue> {
+    match row.get(OM_CODE_FIELD) {
+        Some(om_code) if om_code.1.trim().is_empty() => {
+            vec![row.issue(om_code, RowProblem::MissingOmCode)]
+        }
+        _ => Vec::new(),
+    }
+}
+
+#[test]
+fn validate_grade_rows() {
+    let headers = StringRecord::from(vec![
+        OM_CODE_FIELD,
+        SUBJECT_CATEGORY_FIELD,
+        SUBJECT_FIELD,
+        THEME_FIELD,
+        GRADE_FIELD,
+        DATE_FIELDS[0],
+        DATE_FIELDS[1],
+    ]);
+    let source_headers = StringRecord::from(vec![
+        "Tanuló azonosítója",
+        "Tárgy kategória",
+        "Tantárgy",
+        "Téma",
+        "Jegy",
+        "Bejegyzés dátuma",
+        "Rögzítés dátuma",
+    ]);
+    let valid = StringRecord::from(vec![
+        "716",
+        "Biológia",
+        "biológia",
+        "Sejtek",
+        "5",
+        "2025.09.03.",
+        "2025.09.03.",
+    ]);
+    let text_only = StringRecord::from(vec![
+        "716",
+        "Biológia",
+        "biológia",
+        " - ",
+        " - ",
+        "2025.09.03.",
+        "2025.09.03",
+    ]);
+    let invalid = StringRecord::from(vec!["", "", " ", "", "7", "2025.13.03.", "tegnap"]);
+    let row = |line, record| Row {
+        line,
+        headers: &headers,
+        source_headers: &source_headers,
+        record,
+    };
+
+    assert!(validate_grade(&row(2, &valid)).is_empty());
+    assert!(validate_grade(&row(3, &text_only)).is_empty());
+    let issues = validate_grade(&row(4, &invalid));
+    let problems = issues.iter().map(|i| &i.problem).collect::<Vec<_>>();
+    assert_eq!(
+        problems,
+        [
+            &RowProblem::MissingOmCode,
+            &RowProblem::EmptySubject,
+            &RowProblem::EmptySubjectCategory,
+            &RowProblem::EmptyTheme,
+            &RowProblem::InvalidDate,
+            &RowProblem::InvalidDate,
+            &RowProblem::GradeOutOfRange
+        ]
+    );
+    assert_eq!(
+        issues[6].to_string(),
+        "line 4, \"Jegy\" = \"7\": grade outside 1-5"
+    );
+}
+
+#[test]
+fn leave_out_invalid_rows() {
+    let parsed = crate::grades_processor::fixtures::grades_of_rows(
+        "invalid-rows",
+        &[
+            "Aladar Barnabas;10.C;2007.01.01;71624229433;Biológia;biológia;10.C;Makacs Tímea; - ;Órai munka;Jeles(5);5; - ;; - ; - ;2025.09.03.;2025.09.03.;",
+            "Aladar Barnabas;10.C;2007.01.01;71624229433;Biológia;biológia;10.C;Makacs Tímea; - ;Órai munka;Jeles(5);7; - ;; - ; - ;2025.09.03.;2025.09.03.;",
+        ],
+    );
+    // the valid row is kept, the invalid one is reported by its line
+    assert_eq!(parsed.data.values().map(Vec::len).sum::<usize>(), 1);
+    assert_eq!(
+        parsed
+            .issues
+            .iter()
+            .map(|i| (i.line, i.column.as_deref(), &i.problem))
+            .collect::<Vec<_>>(),
+        [(3, Some("Jegy"), &RowProblem::GradeOutOfRange)]
+    );
+}
@@ -7,6 +7,7 @@ export const AppRouter = () => {
     const WindowLayout = lazy(() => import("./layouts/windowLayout"));
 
     const GradeImportPage = lazy(() => import("./pages/gradeImportPage"));
+    const HistoryPage = lazy(() => import("./pages/historyPage"));
     const SecurityPage = lazy(() => import("./pages/securityPage"));
     const SettingsPage = lazy(() => import("./pages/settingsPage"));
     const StatusPage = lazy(() => import("./pages/statusPage"));
@@ -16,6 +17,7 @@ export const AppRouter = () => {
             <Routes>
                 <Route element={<WindowLayout />}>
                     <Route path="/" element={<GradeImportPage />} />
+                    <Route path="/history" element={<HistoryPage />} />
                     <Route path="/security" element={<SecurityPage />} />
                     <Route path="/settings" element={<SettingsPage />} />
                     <Route path="/status" element={<StatusPage />} />
@@ -1,5 +1,5 @@
 import { AppShell, Navbar, Stack, UnstyledButton, createStyles, rem } from "@mantine/core";
-import { IconDatabaseImport, IconKey, IconSatellite, IconSettings } from "@tabler/icons-react";
+import { IconDatabaseImport, IconHistory, IconKey, IconSatellite, IconSettings } from "@tabler/icons-react";
 import { Outlet, useNavigate } from "react-router-dom";
 
 import { ColorSchemeToggle } from "../../components/colorSchemeToggle";
@@ -46,6 +46,7 @@ const useStyles = createStyles((theme) => ({
 
 const links = [
     { icon: IconDatabaseImport, path: "/" },
+    { icon: IconHistory, path: "/history" },
     { icon: IconKey, path: "/security" },
     { icon: IconSatellite, path: "/status" },
 ];
@@ -1,56 +1,334 @@
-import { Button, FileInput, Group, Progress, Stack, Text, Title } from "@mantine/core";
+import { Button, Checkbox, FileInput, Group, List, Progress, Stack, Text, TextInput, Title } from "@mantine/core";
 import { UnlistenFn, listen } from "@tauri-apps/api/event";
 import { useEffect, useState } from "react";
 
 import { IconCheck } from "@tabler/icons-react";
 import { invoke } from "@tauri-apps/api/core";
 import { notifications } from "@mantine/notifications";
-import { open } from "@tauri-apps/plugin-dialog";
+import { ask, open, save } from "@tauri-apps/plugin-dialog";
 import { preferencesStore } from "../../preferencesStore";
 import { useSecurityStore } from "../../stores/securityStore";
 import { useSettingStore } from "../../stores/settingsStore";
 
+interface PreviewUser {
+    id: string;
+    studentName: string;
+    schoolClass: string | null;
+    gradeCount: number;
+    infoFromGrades: boolean;
+    unchanged: boolean;
+}
+
+interface PreviewStudent {
+    studentName: string;
+    schoolClass: string | null;
+    gradeCount: number;
+}
+
+type UserOutcome =
+    | { kind: "success" }
+    | { kind: "skipped" }
+    | { kind: "alreadyImported" }
+    | { kind: "unchanged" }
+    | { kind: "pendingGrades" }
+    | { kind: "cancelled" }
+    | { kind: "invalidRecord"; message: string }
+    | { kind: "encryptionError"; cause: CryptoErrorKind | null; message: string }
+    | { kind: "httpError"; status: number | null; message: string; problem: { title?: string | null; detail?: string | null } | null };
+
+interface UserImportResult {
+    userId: string;
+    studentName: string | null;
+    schoolClass: string | null;
+    outcome: UserOutcome;
+}
+
+interface ImportReport {
+    results: UserImportResult[];
+}
+
+const isFailure = (outcome: UserOutcome) =>
+    outcome.kind === "invalidRecord" || outcome.kind === "encryptionError" || outcome.kind === "httpError";
+
+type CryptoErrorKind =
+    | "base64"
+    | "keyLength"
+    | "kyber"
+    | "serialization"
+    | "malformedEnvelope"
+    | "decryption"
+    | "kdf"
+    | "kdfLimits";
+
+const cryptoErrorNames: Record<CryptoErrorKind, string> = {
+    base64: "hibás (nem base64) nyilvános kulcs",
+    keyLength: "hibás hosszúságú kulcs",
+    kyber: "érvénytelen Kyber nyilvános kulcs",
+    serialization: "az adatok nem alakíthatók JSON-né",
+    malformedEnvelope: "hiányos titkosított adat",
+    decryption: "hibás kulcs vagy sérült titkosított adat",
+    kdf: "hibás kulcsszármaztatási paraméterek",
+    kdfLimits: "túl nagy kulcsszármaztatási paraméterek",
+};
+
+const describeFailure = (outcome: UserOutcome): string => {
+    if (outcome.kind === "invalidRecord") return `Hibás felhasználói adat a szerveren: ${outcome.message}`;
+    if (outcome.kind === "encryptionError")
+        return `Titkosítási hiba: ${outcome.cause ? cryptoErrorNames[outcome.cause] : outcome.message}`;
+    if (outcome.kind === "httpError")
+        return `${outcome.status ?? "Nem elérhető szerver"}: ${outcome.problem?.detail ?? outcome.problem?.title ?? outcome.message}`;
+    return "";
+};
+
+type InputFormat =
+    | { kind: "xlsx" }
+    | {
+          kind: "csv";
+          delimiter: string;
+          encoding: "utf8" | "utf8Bom" | "utf16Le" | "utf16Be" | "windows1250";
+      };
+
+interface DetectedFormat {
+    file: "grades" | "students";
+    format: InputFormat;
+}
+
+const encodingNames = {
+    utf8: "UTF-8",
+    utf8Bom: "UTF-8 (BOM)",
+    utf16Le: "UTF-16LE",
+    utf16Be: "UTF-16BE",
+    windows1250: "Windows-1250",
+};
+
+const describeFormat = (format: InputFormat): string => {
+    if (format.kind === "xlsx") return "xlsx";
+    const delimiter = format.delimiter === "\t" ? "tabulátor" : `"${format.delimiter}"`;
+    return `csv, elválasztó: ${delimiter}, kódolás: ${encodingNames[format.encoding]}`;
+};
+
+interface RowIssue {
+    file: string | null;
+    line: number;
+    column: string | null;
+    value: string | null;
+    problem:
+        | { kind: "missingOmCode" }
+        | { kind: "invalidDate" }
+        | { kind: "gradeOutOfRange" }
+        | { kind: "emptySubject" }
+        | { kind: "emptySubjectCategory" }
+        | { kind: "emptyTheme" }
+        | { kind: "malformed"; message: string };
+}
+
+interface InputValidation {
+    grades: RowIssue[];
+    students: RowIssue[];
+}
+
+const describeProblem = (problem: RowIssue["problem"]): string => {
+    if (problem.kind === "missingOmCode") return "hiányzó OM azonosító";
+    if (problem.kind === "invalidDate") return "hibás dátum";
+    if (problem.kind === "gradeOutOfRange") return "a jegy nem 1 és 5 közötti";
+    if (problem.kind === "emptySubject") return "üres tantárgy";
+    if (problem.kind === "emptySubjectCategory") return "üres tárgy kategória";
+    if (problem.kind === "emptyTheme") return "üres téma";
+    return problem.message;
+};
+
+const describeIssue = (issue: RowIssue): string =>
+    `${issue.file ? `${issue.file}, ` : ""}${issue.line}. sor${issue.column ? `, ${issue.column}` : ""}${issue.value !== null ? ` ("${issue.value}")` : ""}: ${describeProblem(issue.problem)}`;
+
+interface ImportSummary {
+    processed: number;
+    total: number;
+    cancelled: boolean;
+}
+
+interface InvalidUser {
+    id: string;
+    message: string;
+}
+
+interface FilterSummary {
+    selectedGrades: number;
+    leftOutGrades: number;
+    classes: string[];
+    subjects: string[];
+}
+
+interface ImportPreview {
+    matchedUsers: PreviewUser[];
+    usersWithoutGrades: string[];
+    studentsWithoutAccount: PreviewStudent[];
+    invalidUsers: InvalidUser[];
+    duplicateGrades: number;
+    filter: FilterSummary | null;
+}
+
+interface CoverageReport {
+    numStudents: number;
+    numRegistered: number;
+    unregistered: { studentName: string; schoolClass: string }[];
+}
+
+const splitList = (value: string): string[] =>
+    value
+        .split(",")
+        .map((item) => item.trim())
+        .filter((item) => item !== "");
+
 const GradeImportPage = (): JSX.Element => {
     const security = useSecurityStore();
     const settings = useSettingStore();
 
-    const [gradesFileValue, setGradesFileValue] = useState<File | null>(null);
-    const [gradesFilePath, setGradesFilePath] = useState<string | null>(null);
+    const [gradesFileValue, setGradesFileValue] = useState<File[]>([]);
+    const [gradesFilePaths, setGradesFilePaths] = useState<string[]>([]);
     const [gradesFileError, setGradesFileError] = useState<string | null>(null);
 
     const [studentsFileValue, setStudentsFileValue] = useState<File | null>(null);
     const [studentsFilePath, setStudentsFilePath] = useState<string | null>(null);
 
+    const [changesOnly, setChangesOnly] = useState<boolean>(false);
+
+    const [includeClasses, setIncludeClasses] = useState<string>("");
+    const [excludeClasses, setExcludeClasses] = useState<string>("");
+    const [includeSubjects, setIncludeSubjects] = useState<string>("");
+    const [excludeSubjects, setExcludeSubjects] = useState<string>("");
+    const [includeSubjectCategories, setIncludeSubjectCategories] = useState<string>("");
+    const [excludeSubjectCategories, setExcludeSubjectCategories] = useState<string>("");
+    const [since, setSince] = useState<string>("");
+    const [until, setUntil] = useState<string>("");
+
     const [fileLoading, setFileLoading] = useState<boolean>(false);
     const [fileDisabled, setFileDisabled] = useState<boolean>(false);
 
     const [userCount, setUserCount] = useState<number | undefined>(undefined);
     const [progress, setProgress] = useState<number>(0);
+    const [fetchedCount, setFetchedCount] = useState<number | null>(null);
+    const [inputFormats, setInputFormats] = useState<Partial<Record<DetectedFormat["file"], InputFormat[]>>>({});
+
+    const [preview, setPreview] = useState<ImportPreview | null>(null);
+    const [report, setReport] = useState<ImportReport | null>(null);
+    const [coverage, setCoverage] = useState<CoverageReport | null>(null);
+    const [validation, setValidation] = useState<InputValidation | null>(null);
 
     const [error, setError] = useState<string | null>(null);
 
     useEffect(() => {
         let unlistenProgress: UnlistenFn;
+        let unlistenFetching: UnlistenFn;
+        let unlistenInputFormat: UnlistenFn;
         let unlistenUserCount: UnlistenFn;
+        let unlistenSummary: UnlistenFn;
+        let unlistenRosterProgress: UnlistenFn;
+        let unlistenRosterFetching: UnlistenFn;
+        let unlistenRosterUserCount: UnlistenFn;
 
         (async () => {
             unlistenProgress = await listen("import-progress", (event) => {
                 setProgress(event.payload as number);
             });
+            unlistenFetching = await listen("import-fetching", (event) => {
+                setFetchedCount(event.payload as number);
+            });
+            unlistenInputFormat = await listen("import-input-format", (event) => {
+                const detected = event.payload as DetectedFormat;
+                setInputFormats((formats) => ({
+                    ...formats,
+                    [detected.file]: [...(formats[detected.file] ?? []), detected.format],
+                }));
+            });
             unlistenUserCount = await listen("import-users", (event) => {
                 console.log(event.payload);
+                setFetchedCount(null);
                 setUserCount(event.payload as number);
             });
+            unlistenRosterProgress = await listen("roster-progress", (event) => {
+                setProgress(event.payload as number);
+            });
+            unlistenRosterFetching = await listen("roster-fetching", (event) => {
+                setFetchedCount(event.payload as number);
+            });
+            unlistenRosterUserCount = await listen("roster-users", (event) => {
+                setFetchedCount(null);
+                setUserCount(event.payload as number);
+            });
+            unlistenSummary = await listen("import-summary", (event) => {
+                const summary = event.payload as ImportSummary;
+                if (!summary.cancelled) return;
+                notifications.show({
+                    id: "import-cancelled",
+                    withCloseButton: true,
+                    autoClose: 5000,
+                    title: "Importálás megszakítva",
+                    message: `${summary.total} felhasználóból ${summary.processed} feldolgozva, később folytatható.`,
+                    color: "yellow",
+                });
+            });
         })();
 
         return () => {
             if (unlistenProgress) unlistenProgress();
+            if (unlistenFetching) unlistenFetching();
+            if (unlistenInputFormat) unlistenInputFormat();
             if (unlistenUserCount) unlistenUserCount();
+            if (unlistenSummary) unlistenSummary();
+            if (unlistenRosterProgress) unlistenRosterProgress();
+            if (unlistenRosterFetching) unlistenRosterFetching();
+            if (unlistenRosterUserCount) unlistenRosterUserCount();
         };
     }, []);
 
+    const showError = (error: unknown) => {
+        if (error === "401") setError("Hibás import kulcs!");
+        else if (error === "429") setError("Túl sok kérelem rövid idő alatt!");
+        else if (error === "404") setError("Egy megadott felhasználó nem létezik!");
+        else if (error === "500") setError("Szerver hiba történt!");
+        else if (error === "unknown") setError("Ismeretlen reqwest hiba történt!");
+        else setError(`Nem sikerült feltölteni a jegyeket! (${error})`);
+    };
+
+    const gradeFilter = () => ({
+        includeClasses: splitList(includeClasses),
+        excludeClasses: splitList(excludeClasses),
+        includeSubjects: splitList(includeSubjects),
+        excludeSubjects: splitList(excludeSubjects),
+        includeSubjectCategories: splitList(includeSubjectCategories),
+        excludeSubjectCategories: splitList(excludeSubjectCategories),
+        since: since || null,
+        until: until || null,
+    });
+
+    const previewImport = async () => {
+        if (gradesFilePaths.length === 0) {
+            setGradesFileError("Nincs kiválasztva fájl");
+            return;
+        }
+        setGradesFileError(null);
+        setFileDisabled(true);
+        setError(null);
+        try {
+            const options = {
+                gradesFilePaths,
+                studentsFilePath,
+                blueboardUrl: settings.blueboardUrl,
+                importKey: settings.importKey,
+                mappingProfilePath: settings.mappingProfilePath,
+                omCodePepper: security.omCodePepper || null,
+                filter: gradeFilter(),
+            };
+            setValidation(await invoke<InputValidation>("validate_input", { options }));
+            setPreview(await invoke<ImportPreview>("preview_import", { options }));
+        } catch (error) {
+            showError(error);
+        }
+        setFileDisabled(false);
+    };
+
     const importGrades = async () => {
-        if (gradesFilePath === null) {
+        if (gradesFilePaths.length === 0) {
             setGradesFileError("Nincs kiválasztva fájl");
             return;
         }
@@ -58,16 +336,61 @@ const GradeImportPage = (): JSX.Element => {
         setFileLoading(true);
         setFileDisabled(true);
         setError(null);
+        setReport(null);
         try {
-            await invoke("import_grades", {
-                gradesFilePath,
+            const options = {
+                gradesFilePaths,
                 studentsFilePath,
                 blueboardUrl: settings.blueboardUrl,
                 importKey: settings.importKey,
+                mappingProfilePath: settings.mappingProfilePath,
+                omCodePepper: security.omCodePepper || null,
+                importKeyName: settings.importKeyName || null,
                 resetKeyPassword: security.resetKeyPassword,
                 updateResetKeyPassword: security.updateResetKeyPasswordOnImport,
+                changesOnly,
+                filter: gradeFilter(),
+            };
+            const inputValidation = await invoke<InputValidation>("validate_input", { options });
+            setValidation(inputValidation);
+            const invalidRows = inputValidation.grades.length + inputValidation.students.length;
+            const skipInvalidRows =
+                invalidRows > 0 &&
+                (await ask(`A fájlokban ${invalidRows} hibás sor található, ezek kimaradnak. Importálja a többi sort?`, {
+                    title: "Hibás sorok",
+                    okLabel: "Importálás",
+                    cancelLabel: "Mégse",
+                }));
+            if (invalidRows > 0 && !skipInvalidRows) return;
+            const filtered = Object.values(options.filter).some((value) =>
+                Array.isArray(value) ? value.length > 0 : value !== null
+            );
+            if (
+                filtered &&
+                !(await ask(
+                    "A szerver minden felhasználónak csak a legutóbbi importját tartja meg, amíg be nem jelentkezik, így a szűrt importálás felülírja a még fel nem dolgozott teljes importot, a kimaradó jegyek csak a következő teljes importtal érkeznek meg. Folytatja?",
+                    { title: "Szűrt importálás", okLabel: "Importálás", cancelLabel: "Mégse", kind: "warning" }
+                ))
+            )
+                return;
+            const resumable = await invoke<number | null>("resumable_import", { options });
+            const resume =
+                resumable !== null &&
+                (await ask(
+                    `Egy korábbi, megszakadt importálás ugyanezekkel a fájlokkal már ${resumable} felhasználót feltöltött. Folytatja onnan?`,
+                    { title: "Importálás folytatása", okLabel: "Folytatás", cancelLabel: "Újrakezdés" }
+                ));
+            const importReport = await invoke<ImportReport>("import_grades", {
+                options: { ...options, resume, skipInvalidRows },
             });
+            setReport(importReport);
 
+            if (importReport.results.some((result) => result.outcome.kind === "cancelled")) return;
+            const failed = importReport.results.filter((result) => isFailure(result.outcome)).length;
+            if (failed > 0) {
+                setError(`${failed} felhasználó jegyeit nem sikerült feltölteni!`);
+                return;
+            }
             notifications.show({
                 id: "grades-imported",
                 withCloseButton: true,
@@ -78,19 +401,129 @@ const GradeImportPage = (): JSX.Element => {
                 color: "green",
             });
         } catch (error) {
-            if (error === "401") setError("Hibás import kulcs!");
-            else if (error === "429") setError("Túl sok kérelem rövid idő alatt!");
-            else if (error === "404") setError("Egy megadott felhasználó nem létezik!");
-            else if (error === "500") setError("Szerver hiba történt!");
-            else if (error === "unknown") setError("Ismeretlen reqwest hiba történt!");
-            else setError(`Nem sikerült feltölteni a jegyeket! (${error})`);
+            showError(error);
+        } finally {
+            setTimeout(() => {
+                setFileLoading(false);
+                setFileDisabled(false);
+                setProgress(0);
+                setFetchedCount(null);
+            }, 500);
         }
+    };
 
-        setTimeout(() => {
-            setFileLoading(false);
-            setFileDisabled(false);
-            setProgress(0);
-        }, 500);
+    const syncRoster = async () => {
+        if (studentsFilePath === null) return;
+        setFileLoading(true);
+        setFileDisabled(true);
+        setError(null);
+        setReport(null);
+        try {
+            const options = {
+                gradesFilePaths: [],
+                studentsFilePath,
+                blueboardUrl: settings.blueboardUrl,
+                importKey: settings.importKey,
+                mappingProfilePath: settings.mappingProfilePath,
+                omCodePepper: security.omCodePepper || null,
+            };
+            const inputValidation = await invoke<InputValidation>("validate_input", { options });
+            setValidation(inputValidation);
+            const invalidRows = inputValidation.students.length;
+            const skipInvalidRows =
+                invalidRows > 0 &&
+                (await ask(`A tanulói fájlban ${invalidRows} hibás sor található, ezek kimaradnak. Frissíti a többi tanulót?`, {
+                    title: "Hibás sorok",
+                    okLabel: "Frissítés",
+                    cancelLabel: "Mégse",
+                }));
+            if (invalidRows > 0 && !skipInvalidRows) return;
+            const overwritePendingGrades = await ask(
+                "A szerver minden felhasználónak csak a legutóbbi importját tartja meg, amíg be nem jelentkezik, így a névsor frissítése felülírná a korábban feltöltött, de még fel nem dolgozott jegyeket. Azokat is frissíti, akiknek erről a gépről már töltött fel jegyeket? Amelyiküknek még függő jegyei vannak, azok elvesznek.",
+                { title: "Függő jegyek", okLabel: "Felülírás", cancelLabel: "Kihagyás", kind: "warning" }
+            );
+            const rosterReport = await invoke<ImportReport>("sync_roster", {
+                options: { ...options, skipInvalidRows, overwritePendingGrades },
+            });
+            setReport(rosterReport);
+
+            if (rosterReport.results.some((result) => result.outcome.kind === "cancelled")) return;
+            const failed = rosterReport.results.filter((result) => isFailure(result.outcome)).length;
+            if (failed > 0) {
+                setError(`${failed} felhasználó adatait nem sikerült frissíteni!`);
+                return;
+            }
+            notifications.show({
+                id: "roster-synced",
+                withCloseButton: true,
+                autoClose: 3000,
+                title: "Sikeres frissítés",
+                message: "A felhasználók neve és osztálya sikeresen frissítve!",
+                icon: <IconCheck />,
+                color: "green",
+            });
+        } catch (error) {
+            showError(error);
+        } finally {
+            setTimeout(() => {
+                setFileLoading(false);
+                setFileDisabled(false);
+                setProgress(0);
+                setFetchedCount(null);
+            }, 500);
+        }
+    };
+
+    const checkCoverage = async () => {
+        if (studentsFilePath === null) return;
+        setFileDisabled(true);
+        setError(null);
+        try {
+            const options = {
+                studentsFilePath,
+                blueboardUrl: settings.blueboardUrl,
+                importKey: settings.importKey,
+                mappingProfilePath: settings.mappingProfilePath,
+                omCodePepper: security.omCodePepper || null,
+                skipInvalidRows: true,
+            };
+            setCoverage(await invoke<CoverageReport>("check_coverage", { options }));
+        } catch (error) {
+            showError(error);
+        }
+        setFileDisabled(false);
+    };
+
+    const exportCoverage = async () => {
+        const path = await save({
+            defaultPath: "regisztracio_nelkul.csv",
+            filters: [
+                { name: "Táblázat", extensions: ["csv"] },
+                { name: "JSON", extensions: ["json"] },
+            ],
+        });
+        if (!path) return;
+        try {
+            await invoke("export_coverage_report", { report: coverage, path });
+        } catch (error) {
+            setError(`Nem sikerült menteni a listát! (${error})`);
+        }
+    };
+
+    const exportReport = async () => {
+        const path = await save({
+            defaultPath: "import_eredmeny.csv",
+            filters: [
+                { name: "Táblázat", extensions: ["csv"] },
+                { name: "JSON", extensions: ["json"] },
+            ],
+        });
+        if (!path) return;
+        try {
+            await invoke("export_import_report", { report, path });
+        } catch (error) {
+            setError(`Nem sikerült menteni az eredményt! (${error})`);
+        }
     };
 
     return (
@@ -102,30 +535,33 @@ const GradeImportPage = (): JSX.Element => {
                 onClick={async (event) => {
                     event.preventDefault();
                     setFileDisabled(true);
-                    const path = await open({
-                        multiple: false,
+                    const paths = await open({
+                        multiple: true,
                         filters: [
                             {
                                 name: "Táblázat",
-                                extensions: ["csv"],
+                                extensions: ["csv", "xlsx"],
                             },
                         ],
                     });
                     setFileDisabled(false);
-                    if (path) {
-                        setGradesFilePath(path as string);
-                        setGradesFileValue(new File([], (path as string).split("/").pop() as string));
+                    if (paths && paths.length > 0) {
+                        setGradesFilePaths(paths);
+                        setInputFormats((formats) => ({ ...formats, grades: undefined }));
+                        setGradesFileValue(paths.map((path) => new File([], path.split("/").pop() as string)));
                     }
                 }}
                 value={gradesFileValue}
                 onChange={(value) => {
-                    if (value === null) {
-                        setGradesFilePath(null);
-                        setGradesFileValue(null);
+                    if (value.length === 0) {
+                        setGradesFilePaths([]);
+                        setInputFormats((formats) => ({ ...formats, grades: undefined }));
+                        setGradesFileValue([]);
                     }
                 }}
                 label="Jegyek"
-                description="A diákok jegyeit tartalmazó táblázat"
+                description="A diákok jegyeit tartalmazó táblázat(ok), pl. osztályonként, az ismétlődő jegyek csak egyszer kerülnek feltöltésre"
+                multiple={true}
                 placeholder="Tanulok_evkozi_jegyei.csv"
                 withAsterisk={true}
                 clearable={true}
@@ -141,13 +577,14 @@ const GradeImportPage = (): JSX.Element => {
                         filters: [
                             {
                                 name: "Táblázat",
-                                extensions: ["csv"],
+                                extensions: ["csv", "xlsx"],
                             },
                         ],
                     });
                     setFileDisabled(false);
                     if (path) {
                         setStudentsFilePath(path as string);
+                        setInputFormats((formats) => ({ ...formats, students: undefined }));
                         setStudentsFileValue(new File([], (path as string).split("/").pop() as string));
                     }
                 }}
@@ -155,6 +592,7 @@ const GradeImportPage = (): JSX.Element => {
                 onChange={(value) => {
                     if (value === null) {
                         setStudentsFilePath(null);
+                        setInputFormats((formats) => ({ ...formats, students: undefined }));
                         setStudentsFileValue(null);
                     }
                 }}
@@ -163,16 +601,118 @@ const GradeImportPage = (): JSX.Element => {
                 clearable={true}
                 disabled={fileDisabled}
             />
-            {fileLoading && <Progress value={progress} label={`${progress}%`} size="xl" radius="xl" mt="xs" />}
-            <Button
-                loading={fileLoading}
-                variant="default"
-                sx={{ alignSelf: "center" }}
-                mt="xs"
-                onClick={async () => await importGrades()}
-            >
-                Importálás
-            </Button>
+            <Checkbox
+                label="Csak a legutóbbi importálás óta változott adatú felhasználók feltöltése"
+                checked={changesOnly}
+                disabled={fileDisabled}
+                onChange={(event) => setChangesOnly(event.currentTarget.checked)}
+            />
+            <Group grow={true}>
+                <TextInput
+                    label="Csak ezek az osztályok"
+                    description="Vesszővel elválasztva, mind ha üres"
+                    placeholder="11.B, 12.A"
+                    value={includeClasses}
+                    disabled={fileDisabled}
+                    onChange={(event) => setIncludeClasses(event.currentTarget.value)}
+                />
+                <TextInput
+                    label="Kivéve ezek az osztályok"
+                    description="Vesszővel elválasztva"
+                    value={excludeClasses}
+                    disabled={fileDisabled}
+                    onChange={(event) => setExcludeClasses(event.currentTarget.value)}
+                />
+            </Group>
+            <Group grow={true}>
+                <TextInput
+                    label="Csak ezek a tantárgyak"
+                    description="Vesszővel elválasztva, mind ha üres"
+                    placeholder="Matematika"
+                    value={includeSubjects}
+                    disabled={fileDisabled}
+                    onChange={(event) => setIncludeSubjects(event.currentTarget.value)}
+                />
+                <TextInput
+                    label="Kivéve ezek a tantárgyak"
+                    description="Vesszővel elválasztva"
+                    value={excludeSubjects}
+                    disabled={fileDisabled}
+                    onChange={(event) => setExcludeSubjects(event.currentTarget.value)}
+                />
+            </Group>
+            <Group grow={true}>
+                <TextInput
+                    label="Csak ezek a tantárgykategóriák"
+                    description="Vesszővel elválasztva, mind ha üres"
+                    placeholder="Idegen nyelv"
+                    value={includeSubjectCategories}
+                    disabled={fileDisabled}
+                    onChange={(event) => setIncludeSubjectCategories(event.currentTarget.value)}
+                />
+                <TextInput
+                    label="Kivéve ezek a tantárgykategóriák"
+                    description="Vesszővel elválasztva"
+                    value={excludeSubjectCategories}
+                    disabled={fileDisabled}
+                    onChange={(event) => setExcludeSubjectCategories(event.currentTarget.value)}
+                />
+            </Group>
+            <Group grow={true}>
+                <TextInput
+                    label="Jegyek ettől"
+                    description="Bejegyzés dátuma"
+                    type="date"
+                    value={since}
+                    disabled={fileDisabled}
+                    onChange={(event) => setSince(event.currentTarget.value)}
+                />
+                <TextInput
+                    label="Jegyek eddig"
+                    description="Bejegyzés dátuma"
+                    type="date"
+                    value={until}
+                    disabled={fileDisabled}
+                    onChange={(event) => setUntil(event.currentTarget.value)}
+                />
+            </Group>
+            {fileLoading && fetchedCount !== null && (
+                <Text size="sm" mt="xs" sx={{ alignSelf: "center" }}>
+                    Felhasználók lekérése a szerverről: {fetchedCount}
+                </Text>
+            )}
+            {fileLoading && fetchedCount === null && (
+                <Progress value={progress} label={`${progress}%`} size="xl" radius="xl" mt="xs" />
+            )}
+            <Group position="center" mt="xs">
+                <Button variant="default" disabled={fileDisabled} onClick={async () => await previewImport()}>
+                    Előnézet
+                </Button>
+                <Button loading={fileLoading} variant="default" onClick={async () => await importGrades()}>
+                    Importálás
+                </Button>
+                <Button
+                    variant="default"
+                    disabled={fileDisabled || studentsFilePath === null}
+                    title="Csak a tanulók neve és osztálya kerül feltöltésre, jegyek nélkül (pl. tanév elején)"
+                    onClick={async () => await syncRoster()}
+                >
+                    Névsor frissítése
+                </Button>
+                <Button
+                    variant="default"
+                    disabled={fileDisabled || studentsFilePath === null}
+                    title="A még nem regisztrált tanulók listája, az OM azonosítók nem kerülnek a szerverre"
+                    onClick={async () => await checkCoverage()}
+                >
+                    Regisztráció ellenőrzése
+                </Button>
+                {fileLoading && (
+                    <Button variant="default" color="red" onClick={async () => await invoke("cancel_import")}>
+                        Megszakítás
+                    </Button>
+                )}
+            </Group>
             {error && (
                 <Text color="red" size="sm" sx={{ alignSelf: "center" }}>
                     {error}
@@ -184,6 +724,162 @@ const GradeImportPage = (): JSX.Element => {
                     {userCount ?? "Ismeretlen"}
                 </Text>
             </Group>
+            {inputFormats.grades?.map((format, i) => (
+                <Group position="apart" key={i}>
+                    <Text size="sm">Jegyek formátuma{inputFormats.grades!.length > 1 ? ` (${i + 1}.)` : ""}:</Text>
+                    <Text size="sm">{describeFormat(format)}</Text>
+                </Group>
+            ))}
+            {inputFormats.students && (
+                <Group position="apart">
+                    <Text size="sm">Tanulók formátuma:</Text>
+                    <Text size="sm">{describeFormat(inputFormats.students[inputFormats.students.length - 1])}</Text>
+                </Group>
+            )}
+            {validation && validation.grades.length + validation.students.length > 0 && (
+                <>
+                    <Text size="sm" color="red">
+                        Hibás sorok:
+                    </Text>
+                    <List size="sm">
+                        {validation.grades.map((issue, i) => (
+                            <List.Item key={`grades-${i}`}>Jegyek, {describeIssue(issue)}</List.Item>
+                        ))}
+                        {validation.students.map((issue, i) => (
+                            <List.Item key={`students-${i}`}>Tanulók, {describeIssue(issue)}</List.Item>
+                        ))}
+                    </List>
+                </>
+            )}
+            {coverage && (
+                <>
+                    <Group position="apart">
+                        <Text size="sm">Regisztrált tanulók:</Text>
+                        <Text size="sm" weight="bold">
+                            {coverage.numRegistered} / {coverage.numStudents}
+                        </Text>
+                    </Group>
+                    <List size="sm">
+                        {coverage.unregistered.map((student, i) => (
+                            <List.Item key={i}>
+                                {student.studentName} ({student.schoolClass})
+                            </List.Item>
+                        ))}
+                    </List>
+                    <Button variant="default" sx={{ alignSelf: "center" }} onClick={async () => await exportCoverage()}>
+                        Lista mentése
+                    </Button>
+                </>
+            )}
+            {report && (
+                <>
+                    <Group position="apart">
+                        <Text size="sm">Sikeres / kihagyott / sikertelen:</Text>
+                        <Text size="sm" weight="bold">
+                            {report.results.filter((result) => result.outcome.kind === "success").length} /{" "}
+                            {
+                                report.results.filter(
+                                    (result) =>
+                                        result.outcome.kind === "skipped" ||
+                                        result.outcome.kind === "unchanged" ||
+                                        result.outcome.kind === "pendingGrades"
+                                ).length
+                            }{" "}
+                            /{" "}
+                            {report.results.filter((result) => isFailure(result.outcome)).length}
+                        </Text>
+                    </Group>
+                    <List size="sm">
+                        {report.results
+                            .filter((result) => isFailure(result.outcome))
+                            .map((result) => (
+                                <List.Item key={result.userId}>
+                                    {result.studentName ?? result.userId} ({result.schoolClass ?? "?"}) -{" "}
+                                    {describeFailure(result.outcome)}
+                                </List.Item>
+                            ))}
+                    </List>
+                    <Button variant="default" sx={{ alignSelf: "center" }} onClick={async () => await exportReport()}>
+                        Eredmény mentése
+                    </Button>
+                </>
+            )}
+            {preview && (
+                <>
+                    <Group position="apart">
+                        <Text size="sm">Jegyeket kapó felhasználók:</Text>
+                        <Text size="sm" weight="bold">
+                            {preview.matchedUsers.length} (
+                            {preview.matchedUsers.reduce((sum, user) => sum + user.gradeCount, 0)} jegy)
+                        </Text>
+                    </Group>
+                    {preview.filter && (
+                        <>
+                            <Group position="apart">
+                                <Text size="sm">Szűrés után kimaradó jegyek:</Text>
+                                <Text size="sm" weight="bold">
+                                    {preview.filter.leftOutGrades} /{" "}
+                                    {preview.filter.selectedGrades + preview.filter.leftOutGrades}
+                                </Text>
+                            </Group>
+                            <Text size="sm">Osztályok: {preview.filter.classes.join(", ") || "-"}</Text>
+                            <Text size="sm">Tantárgyak: {preview.filter.subjects.join(", ") || "-"}</Text>
+                        </>
+                    )}
+                    {preview.duplicateGrades > 0 && (
+                        <Group position="apart">
+                            <Text size="sm">Kihagyott ismétlődő jegyek:</Text>
+                            <Text size="sm" weight="bold">
+                                {preview.duplicateGrades}
+                            </Text>
+                        </Group>
+                    )}
+                    <Group position="apart">
+                        <Text size="sm">Legutóbbi importálás óta változatlan adatú felhasználók:</Text>
+                        <Text size="sm" weight="bold">
+                            {preview.matchedUsers.filter((user) => user.unchanged).length}
+                        </Text>
+                    </Group>
+                    <Group position="apart">
+                        <Text size="sm">Jegyek nélküli felhasználók:</Text>
+                        <Text size="sm" weight="bold">
+                            {preview.usersWithoutGrades.length}
+                        </Text>
+                    </Group>
+                    <Text size="sm">Név és osztály a jegyekből:</Text>
+                    <List size="sm">
+                        {preview.matchedUsers
+                            .filter((user) => user.infoFromGrades)
+                            .map((user) => (
+                                <List.Item key={user.id}>
+                                    {user.studentName} ({user.schoolClass ?? "?"})
+                                </List.Item>
+                            ))}
+                    </List>
+                    <Text size="sm">Regisztráció nélküli tanulók:</Text>
+                    <List size="sm">
+                        {preview.studentsWithoutAccount.map((student, i) => (
+                            <List.Item key={i}>
+                                {student.studentName} ({student.schoolClass ?? "?"}) - {student.gradeCount} jegy
+                            </List.Item>
+                        ))}
+                    </List>
+                    {preview.invalidUsers.length > 0 && (
+                        <>
+                            <Text size="sm" color="red">
+                                Kihagyott, hibás felhasználók a szerveren:
+                            </Text>
+                            <List size="sm">
+                                {preview.invalidUsers.map((user, i) => (
+                                    <List.Item key={i}>
+                                        {user.id || "?"} - {user.message}
+                                    </List.Item>
+                                ))}
+                            </List>
+                        </>
+                    )}
+                </>
+            )}
         </Stack>
     );
 };
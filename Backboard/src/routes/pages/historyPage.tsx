@@ -0,0 +1,166 @@
+import { Button, Checkbox, Group, Select, Stack, Table, Text, TextInput, Title } from "@mantine/core";
+import { useEffect, useState } from "react";
+
+import { invoke } from "@tauri-apps/api/core";
+import { useSettingStore } from "../../stores/settingsStore";
+
+type RunResult = "completed" | "partiallyFailed" | "cancelled" | "failed";
+
+interface HistoryEntry {
+    id: number;
+    startedAt: string;
+    finishedAt: string;
+    blueboardUrl: string;
+    importKeyName: string | null;
+    importKeyHash: string;
+    inputFiles: { path: string; sha256: string | null }[];
+    counts: {
+        succeeded: number;
+        skipped: number;
+        unchanged: number;
+        alreadyImported: number;
+        cancelled: number;
+        failed: number;
+    };
+    result: RunResult;
+    error: string | null;
+    classes: string[];
+}
+
+const resultNames: Record<RunResult, string> = {
+    completed: "Sikeres",
+    partiallyFailed: "Részben sikertelen",
+    cancelled: "Megszakítva",
+    failed: "Sikertelen",
+};
+
+const resultColors: Record<RunResult, string> = {
+    completed: "green",
+    partiallyFailed: "yellow",
+    cancelled: "yellow",
+    failed: "red",
+};
+
+const HistoryPage = (): JSX.Element => {
+    const blueboardUrl = useSettingStore((state) => state.blueboardUrl);
+
+    const [schoolClass, setSchoolClass] = useState<string>("");
+    const [since, setSince] = useState<string>("");
+    const [until, setUntil] = useState<string>("");
+    const [result, setResult] = useState<RunResult | null>(null);
+    const [currentServerOnly, setCurrentServerOnly] = useState<boolean>(true);
+
+    const [entries, setEntries] = useState<HistoryEntry[]>([]);
+    const [error, setError] = useState<string | null>(null);
+
+    const listHistory = async () => {
+        try {
+            const filter = {
+                blueboardUrl: currentServerOnly ? blueboardUrl : null,
+                schoolClass: schoolClass || null,
+                since: since || null,
+                until: until || null,
+                result,
+            };
+            setEntries(await invoke<HistoryEntry[]>("list_import_history", { filter }));
+            setError(null);
+        } catch (error) {
+            setError(`Nem sikerült betölteni az előzményeket! (${error})`);
+        }
+    };
+
+    useEffect(() => {
+        listHistory();
+    }, [blueboardUrl]);
+
+    return (
+        <Stack spacing="xs">
+            <Title order={2} size="h1">
+                Előzmények
+            </Title>
+            <Group grow={true}>
+                <TextInput
+                    label="Osztály"
+                    description="Feltöltött jegyekkel"
+                    placeholder="10.C"
+                    value={schoolClass}
+                    onChange={(event) => setSchoolClass(event.currentTarget.value)}
+                />
+                <Select
+                    label="Eredmény"
+                    description="Bármilyen, ha üres"
+                    clearable={true}
+                    value={result}
+                    onChange={(value) => setResult(value as RunResult | null)}
+                    data={Object.entries(resultNames).map(([value, label]) => ({ value, label }))}
+                />
+            </Group>
+            <Group grow={true}>
+                <TextInput
+                    label="Ettől"
+                    type="date"
+                    value={since}
+                    onChange={(event) => setSince(event.currentTarget.value)}
+                />
+                <TextInput
+                    label="Eddig"
+                    type="date"
+                    value={until}
+                    onChange={(event) => setUntil(event.currentTarget.value)}
+                />
+            </Group>
+            <Checkbox
+                label="Csak a beállított szerverre"
+                checked={currentServerOnly}
+                onChange={(event) => setCurrentServerOnly(event.currentTarget.checked)}
+            />
+            <Button variant="default" sx={{ alignSelf: "center" }} onClick={async () => await listHistory()}>
+                Keresés
+            </Button>
+            {error && (
+                <Text color="red" size="sm" sx={{ alignSelf: "center" }}>
+                    {error}
+                </Text>
+            )}
+            {entries.length === 0 && error === null && (
+                <Text size="sm" sx={{ alignSelf: "center" }}>
+                    Nincs a feltételeknek megfelelő importálás.
+                </Text>
+            )}
+            {entries.length > 0 && (
+                <Table fontSize="sm" striped={true}>
+                    <thead>
+                        <tr>
+                            <th>Kezdés</th>
+                            <th>Import kulcs</th>
+                            <th>Sikeres / kihagyott / sikertelen</th>
+                            <th>Osztályok</th>
+                            <th>Eredmény</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {entries.map((entry) => (
+                            <tr key={entry.id}>
+                                <td>{new Date(entry.startedAt).toLocaleString("hu-HU")}</td>
+                                <td>{entry.importKeyName ?? entry.importKeyHash}</td>
+                                <td>
+                                    {entry.counts.succeeded} /{" "}
+                                    {entry.counts.skipped + entry.counts.unchanged + entry.counts.alreadyImported} /{" "}
+                                    {entry.counts.failed}
+                                </td>
+                                <td>{entry.classes.join(", ")}</td>
+                                <td>
+                                    <Text size="sm" color={resultColors[entry.result]} title={entry.error ?? undefined}>
+                                        {resultNames[entry.result]}
+                                    </Text>
+                                </td>
+                            </tr>
+                        ))}
+                    </tbody>
+                </Table>
+            )}
+        </Stack>
+    );
+};
+
+export default HistoryPage;
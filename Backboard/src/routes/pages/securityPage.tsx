@@ -1,4 +1,4 @@
-import { Button, Checkbox, Stack, Text, Title } from "@mantine/core";
+import { Button, Checkbox, PasswordInput, Stack, Text, Title } from "@mantine/core";
 import { notifications, showNotification } from "@mantine/notifications";
 import { useEffect, useState } from "react";
 
@@ -68,6 +68,15 @@ const SecurityPage = (): JSX.Element => {
                     await preferencesStore.save();
                 }}
             />
+            <PasswordInput
+                label="OM azonosító pepper"
+                description="A szerver titkos kulcsa az OM azonosítók hasheléséhez, enélkül csak a régi hash szerint párosíthatók a felhasználók"
+                value={security.omCodePepper}
+                onChange={async (event) => {
+                    security.setOmCodePepper(event.currentTarget.value);
+                    await preferencesStore.save();
+                }}
+            />
             <Button
                 loading={uploadLoading}
                 disabled={settings.blueboardUrl === "" || security.resetKeyPassword === "" || settings.importKey === ""}
@@ -1,7 +1,11 @@
-import { Center, Divider, Group, Loader, Stack, Switch, Text, TextInput, Title } from "@mantine/core";
+import { Button, Center, Divider, Group, Loader, Stack, Switch, Text, TextInput, Title } from "@mantine/core";
 import { disable, enable, isEnabled } from "@tauri-apps/plugin-autostart";
+import { open, save } from "@tauri-apps/plugin-dialog";
 import { useEffect, useState } from "react";
 
+import { invoke } from "@tauri-apps/api/core";
+import { notifications } from "@mantine/notifications";
+
 import { preferencesStore } from "../../preferencesStore";
 import { useSettingStore } from "../../stores/settingsStore";
 
@@ -23,6 +27,36 @@ const SettingsPage = (): JSX.Element => {
         await preferencesStore.save();
     };
 
+    const chooseMappingProfile = async () => {
+        const path = await open({
+            multiple: false,
+            filters: [{ name: "Profil", extensions: ["toml", "json"] }],
+        });
+        if (!path) return;
+        settings.setMappingProfilePath(path as string);
+        await saveSettings();
+    };
+
+    const exportDefaultMappingProfile = async () => {
+        const path = await save({
+            defaultPath: "kreta_profil.toml",
+            filters: [{ name: "Profil", extensions: ["toml"] }],
+        });
+        if (!path) return;
+        try {
+            await invoke("export_default_mapping_profile", { path });
+        } catch (error) {
+            notifications.show({
+                id: "mapping-profile-export-failed",
+                withCloseButton: true,
+                autoClose: 5000,
+                title: "Sikertelen mentés",
+                message: `${error}`,
+                color: "red",
+            });
+        }
+    };
+
     return (
         <Stack spacing="xs">
             <Title order={2} size="h1">
@@ -52,6 +86,40 @@ const SettingsPage = (): JSX.Element => {
                         onBlur={async () => await saveSettings()}
                         withAsterisk={true}
                     />
+                    <TextInput
+                        label="Import kulcs neve"
+                        description="Az importálási előzményekben jelenik meg, hogy kiderüljön, melyik kulccsal történt"
+                        value={settings.importKeyName}
+                        onChange={(event) => settings.setImportKeyName(event.currentTarget.value)}
+                        onBlur={async () => await saveSettings()}
+                    />
+                    <TextInput
+                        label="Oszlop-megfeleltetési profil"
+                        description="Az E-Kréta exportok oszlopainak nevei, ha változnának (üresen a beépített profil)"
+                        value={settings.mappingProfilePath ?? ""}
+                        placeholder="Beépített (E-Kréta)"
+                        readOnly={true}
+                        onClick={async () => await chooseMappingProfile()}
+                        rightSectionWidth={90}
+                        rightSection={
+                            settings.mappingProfilePath && (
+                                <Button
+                                    variant="subtle"
+                                    size="xs"
+                                    onClick={async (event) => {
+                                        event.stopPropagation();
+                                        settings.setMappingProfilePath(null);
+                                        await saveSettings();
+                                    }}
+                                >
+                                    Beépített
+                                </Button>
+                            )
+                        }
+                    />
+                    <Button variant="default" sx={{ alignSelf: "flex-start" }} onClick={async () => await exportDefaultMappingProfile()}>
+                        Beépített profil mentése szerkesztéshez
+                    </Button>
                     <Divider variant="dashed" />
                     <Group position="apart">
                         <Text size="sm">Automatikus indítás</Text>
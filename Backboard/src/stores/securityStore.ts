@@ -6,8 +6,10 @@ import { preferencesStorage } from "../preferencesStore";
 interface SecurityState {
     resetKeyPassword: string;
     updateResetKeyPasswordOnImport: boolean;
+    omCodePepper: string;
     setResetKeyPassword(url: string): void;
     setUpdateResetKeyPasswordOnImport(value: boolean): void;
+    setOmCodePepper(pepper: string): void;
 }
 
 export const useSecurityStore = create<SecurityState>()(
@@ -15,8 +17,10 @@ export const useSecurityStore = create<SecurityState>()(
         (set) => ({
             resetKeyPassword: "",
             updateResetKeyPasswordOnImport: true,
+            omCodePepper: "",
             setResetKeyPassword: (url: string) => set({ resetKeyPassword: url }),
-            setUpdateResetKeyPasswordOnImport: (value: boolean) => set({ updateResetKeyPasswordOnImport: value })
+            setUpdateResetKeyPasswordOnImport: (value: boolean) => set({ updateResetKeyPasswordOnImport: value }),
+            setOmCodePepper: (pepper: string) => set({ omCodePepper: pepper })
         }), {
             name: "security",
             storage: createJSONStorage(() => preferencesStorage)
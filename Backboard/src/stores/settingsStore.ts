@@ -8,6 +8,10 @@ interface SettingsState {
     setBlueboardUrl(url: string): void;
     importKey: string;
     setImportKey(key: string): void;
+    importKeyName: string;
+    setImportKeyName(name: string): void;
+    mappingProfilePath: string | null;
+    setMappingProfilePath(path: string | null): void;
 }
 
 export const useSettingStore = create<SettingsState>()(
@@ -17,6 +21,10 @@ export const useSettingStore = create<SettingsState>()(
             setBlueboardUrl: (url: string) => set({ blueboardUrl: url }),
             importKey: "",
             setImportKey: (key: string) => set({ importKey: key }),
+            importKeyName: "",
+            setImportKeyName: (name: string) => set({ importKeyName: name }),
+            mappingProfilePath: null,
+            setMappingProfilePath: (path: string | null) => set({ mappingProfilePath: path }),
         }), {
             name: "settings",
             storage: createJSONStorage(() => preferencesStorage)
@@ -1,3 +1,4 @@
+using System.Text.Json;
 using FluentAssertions;
 using Helpers.Cryptography.Implementations;
 using NUnit.Framework;
@@ -31,4 +32,18 @@ public class KyberKeypairTests
 
         decrypted.Should().BeEquivalentTo("some data");
     }
-}
\ No newline at end of file
+
+    [Test]
+    public void ShouldDecryptBackboardEnvelope()
+    {
+        // encrypted by the Backboard client, the same file its own tests check
+        var vectors = JsonDocument
+            .Parse(File.ReadAllText(Path.Combine(TestContext.CurrentContext.TestDirectory, "test_envelopes.json")))
+            .RootElement;
+
+        var keypair = new KyberKeypair(vectors.GetProperty("privateKey").GetString());
+        var decrypted = keypair.Decrypt(vectors.GetProperty("v1").GetString()!);
+
+        decrypted.Should().Be(vectors.GetProperty("plaintext").GetString());
+    }
+}